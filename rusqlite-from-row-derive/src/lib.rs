@@ -1,11 +1,17 @@
-use std::borrow::Cow;
+mod case;
+mod table;
 
+use std::{borrow::Cow, ops::RangeInclusive};
+
+use case::RenameRule;
 use proc_macro::TokenStream;
-use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use quote::{quote, ToTokens};
 use syn::{
-    parse_macro_input, parse_str, spanned::Spanned, Attribute, Data, DataStruct, DeriveInput,
-    Error, ExprPath, Field, Fields, LitStr, Result, Type,
+    meta::ParseNestedMeta, parse::Parser, parse_macro_input, parse_quote, parse_str,
+    punctuated::Punctuated, spanned::Spanned, token, Attribute, Data, DataStruct, DeriveInput,
+    Error, ExprPath, Field, Fields, GenericArgument, Ident, LitInt, LitStr, PathArguments,
+    PathSegment, Result, Token, Type, WherePredicate,
 };
 
 /// Calls the fallible entry point and writes any errors to the tokenstream.
@@ -25,10 +31,36 @@ fn try_derive_from_row(input: DeriveInput) -> Result<TokenStream2> {
     Ok(from_row_derive.generate())
 }
 
+/// Calls the fallible entry point and writes any errors to the tokenstream.
+#[proc_macro_derive(Table, attributes(table, from_row))]
+pub fn derive_table(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+
+    try_derive_table(derive_input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Fallible entry point for generating a `Table` implementation
+fn try_derive_table(input: DeriveInput) -> Result<TokenStream2> {
+    let table_derive = table::DeriveTable::parse(input)?;
+
+    Ok(table_derive.generate())
+}
+
 /// Main struct for deriving `FromRow` for a struct.
 struct DeriveFromRow {
     ident: syn::Ident,
     generics: syn::Generics,
+    /// The lifetime of borrowed fields, a struct with `#[from_row(borrow)]` implements
+    /// `FromRowRef` instead of `FromRow`.
+    lifetime: Option<syn::Lifetime>,
+    /// Function called with a mutable reference after constructing the struct.
+    after: Option<ExprPath>,
+    /// Function called with a reference after constructing the struct, that can reject it.
+    validate: Option<ExprPath>,
+    /// Where clause predicates from `#[from_row(bound = "..")]` that replace all generated ones.
+    bound: Option<Vec<WherePredicate>>,
     data: Vec<FromRowField>,
 }
 
@@ -37,6 +69,7 @@ impl DeriveFromRow {
         let DeriveInput {
             ident,
             generics,
+            attrs,
             data:
                 Data::Struct(DataStruct {
                     fields: Fields::Named(fields),
@@ -51,20 +84,81 @@ impl DeriveFromRow {
             ));
         };
 
+        let container = ContainerAttrs::parse(attrs)?;
+
+        let lifetime = match container.borrow {
+            Some(span) => {
+                let mut lifetimes = generics.lifetimes();
+
+                match (lifetimes.next(), lifetimes.next()) {
+                    (Some(param), None) => Some(param.lifetime.clone()),
+                    _ => return Err(Error::new(
+                        span,
+                        "`borrow` requires exactly one lifetime parameter, the lifetime of the row",
+                    )),
+                }
+            }
+            None => None,
+        };
         let mut data = Vec::new();
 
         for field in fields.named {
-            data.push(FromRowField::parse(field)?);
+            data.push(FromRowField::parse(field, &container, lifetime.as_ref())?);
+        }
+
+        let count = |role| data.iter().filter(|field| field.tree == Some(role)).count();
+
+        if count(TreeRole::Key) > 1 {
+            return Err(Error::new(
+                ident.span(),
+                "a struct can have at most one `key` field",
+            ));
+        }
+
+        if count(TreeRole::ParentKey) + count(TreeRole::Children) > 0 {
+            for role in [TreeRole::Key, TreeRole::ParentKey, TreeRole::Children] {
+                let count = count(role);
+
+                if count != 1 {
+                    return Err(Error::new(
+                        ident.span(),
+                        format!(
+                            "a tree node should have exactly one `{}` field, found {count}",
+                            role.name()
+                        ),
+                    ));
+                }
+            }
+
+            let parent = data
+                .iter()
+                .find(|field| field.tree == Some(TreeRole::ParentKey))
+                .expect("validated above");
+
+            if option_inner(&parent.ty).is_none() {
+                return Err(Error::new(
+                    parent.ty.span(),
+                    "`parent_key` should be an `Option` of the type of the `key`",
+                ));
+            }
         }
 
         Ok(Self {
             ident,
             generics,
+            lifetime,
+            after: container.after,
+            validate: container.validate,
+            bound: container.bound,
             data,
         })
     }
 
     fn predicates(&self) -> Vec<TokenStream2> {
+        if let Some(bound) = &self.bound {
+            return bound.iter().map(ToTokens::to_token_stream).collect();
+        }
+
         let mut predicates = Vec::new();
 
         for field in &self.data {
@@ -74,7 +168,79 @@ impl DeriveFromRow {
         predicates
     }
 
-    /// Generate the `FromRow` implementation.
+    /// Generate the body that returns the constructed struct, after calling the `after` and
+    /// `validate` hooks on it.
+    fn generate_construct(&self, value: TokenStream2) -> TokenStream2 {
+        if self.after.is_none() && self.validate.is_none() {
+            return quote!(Ok(#value));
+        }
+
+        let mutability = self.after.as_ref().map(|_| quote!(mut));
+        let after = self.after.iter();
+        let validate = self.validate.iter();
+        let type_name = self.ident.to_string();
+
+        quote! {
+            let #mutability value = #value;
+            #(#after(&mut value);)*
+            #(
+                if let Err(err) = #validate(&value) {
+                    return Err(rusqlite_from_row::ValidationError::new(#type_name, err).into());
+                }
+            )*
+            Ok(value)
+        }
+    }
+
+    /// Generate the `Keyed` implementation when the struct has a field marked as `key`.
+    fn generate_keyed(&self) -> Option<TokenStream2> {
+        let key = self
+            .data
+            .iter()
+            .find(|field| field.tree == Some(TreeRole::Key))?;
+
+        let ident = &self.ident;
+        let key_ty = &key.ty;
+        let key = &key.ident;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+
+        Some(quote! {
+            impl #impl_generics rusqlite_from_row::Keyed for #ident #ty_generics #where_clause {
+                type Key = #key_ty;
+
+                fn key(&self) -> &Self::Key {
+                    &self.#key
+                }
+            }
+        })
+    }
+
+    /// Generate the `TreeNode` implementation when the struct has fields marked as `key`,
+    /// `parent_key` and `children`.
+    fn generate_tree_node(&self) -> Option<TokenStream2> {
+        let field = |role| self.data.iter().find(|field| field.tree == Some(role));
+
+        let parent_key = &field(TreeRole::ParentKey)?.ident;
+        let children = &field(TreeRole::Children)?.ident;
+
+        let ident = &self.ident;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+
+        Some(quote! {
+            impl #impl_generics rusqlite_from_row::tree::TreeNode for #ident #ty_generics #where_clause {
+                fn parent_key(&self) -> std::option::Option<&Self::Key> {
+                    std::option::Option::as_ref(&self.#parent_key)
+                }
+
+                fn children_mut(&mut self) -> &mut std::vec::Vec<Self> {
+                    &mut self.#children
+                }
+            }
+        })
+    }
+
+    /// Generate the `FromRow` implementation, or the `FromRowRef` implementation when the struct
+    /// borrows from the row.
     fn generate(self) -> TokenStream2 {
         let ident = &self.ident;
 
@@ -85,21 +251,91 @@ impl DeriveFromRow {
         let is_all_null_fields = self.data.iter().filter_map(|f| f.generate_is_all_null());
 
         let try_from_row_fields = self.data.iter().map(|f| f.generate_try_from_row());
+        let construct = self.generate_construct(quote!(Self { #(#try_from_row_fields),* }));
+
+        let keyed = self.generate_keyed();
+        let tree_node = self.generate_tree_node();
+
+        if let Some(lifetime) = &self.lifetime {
+            return quote! {
+                #keyed
+                #tree_node
+
+                impl #impl_generics rusqlite_from_row::FromRowRef<#lifetime> for #ident #ty_generics where #(#original_predicates),* #(#predicates),* {
+                    #[allow(unused_variables)]
+                    fn try_from_row_ref_prefixed(
+                        row: &#lifetime rusqlite_from_row::rusqlite::Row<'_>,
+                        prefix: Option<&str>,
+                        offset: usize
+                    ) -> std::result::Result<Self, rusqlite_from_row::rusqlite::Error> {
+                        let depth: usize = 0;
+                        #construct
+                    }
+
+                    #[allow(unused_variables)]
+                    fn is_all_null_ref(
+                        row: &#lifetime rusqlite_from_row::rusqlite::Row<'_>,
+                        prefix: Option<&str>,
+                        offset: usize
+                    ) -> std::result::Result<bool, rusqlite_from_row::rusqlite::Error> {
+                        let depth: usize = 0;
+                        Ok(#(#is_all_null_fields)&&*)
+                    }
+                }
+            };
+        }
 
         quote! {
+            #keyed
+            #tree_node
+
             impl #impl_generics rusqlite_from_row::FromRow for #ident #ty_generics where #(#original_predicates),* #(#predicates),* {
                 fn try_from_row_prefixed(
                     row: &rusqlite_from_row::rusqlite::Row,
                     prefix: Option<&str>
                 ) -> std::result::Result<Self, rusqlite_from_row::rusqlite::Error> {
-                    Ok(Self {
-                        #(#try_from_row_fields),*
-                    })
+                    <Self as rusqlite_from_row::FromRow>::try_from_row_offset(row, prefix, 0)
+                }
+
+                fn try_from_row_offset(
+                    row: &rusqlite_from_row::rusqlite::Row,
+                    prefix: Option<&str>,
+                    offset: usize
+                ) -> std::result::Result<Self, rusqlite_from_row::rusqlite::Error> {
+                    <Self as rusqlite_from_row::FromRow>::try_from_row_depth(row, prefix, offset, 0)
                 }
 
                 fn is_all_null(
                     row: &rusqlite_from_row::rusqlite::Row,
                     prefix: Option<&str>
+                ) -> std::result::Result<bool, rusqlite_from_row::rusqlite::Error> {
+                    <Self as rusqlite_from_row::FromRow>::is_all_null_offset(row, prefix, 0)
+                }
+
+                fn is_all_null_offset(
+                    row: &rusqlite_from_row::rusqlite::Row,
+                    prefix: Option<&str>,
+                    offset: usize
+                ) -> std::result::Result<bool, rusqlite_from_row::rusqlite::Error> {
+                    <Self as rusqlite_from_row::FromRow>::is_all_null_depth(row, prefix, offset, 0)
+                }
+
+                #[allow(unused_variables)]
+                fn try_from_row_depth(
+                    row: &rusqlite_from_row::rusqlite::Row,
+                    prefix: Option<&str>,
+                    offset: usize,
+                    depth: usize
+                ) -> std::result::Result<Self, rusqlite_from_row::rusqlite::Error> {
+                    #construct
+                }
+
+                #[allow(unused_variables)]
+                fn is_all_null_depth(
+                    row: &rusqlite_from_row::rusqlite::Row,
+                    prefix: Option<&str>,
+                    offset: usize,
+                    depth: usize
                 ) -> std::result::Result<bool, rusqlite_from_row::rusqlite::Error> {
                     Ok(#(#is_all_null_fields)&&*)
                 }
@@ -115,17 +351,156 @@ struct FromRowField {
     /// The type specified in this field.
     ty: syn::Type,
     attrs: FromRowAttrs,
+    /// Where clause predicates from `#[from_row(bound = "..")]` that replace the generated ones.
+    bound: Option<Vec<WherePredicate>>,
+    /// The role of this field when the struct is a node of a tree.
+    tree: Option<TreeRole>,
+    /// The casing rule from `#[from_row(rename_all = "..")]` on the struct.
+    rename_all: Option<RenameRule>,
+    /// The lifetime of the struct, if any.
+    lifetime: Option<syn::Lifetime>,
 }
 
 impl FromRowField {
-    pub fn parse(field: Field) -> Result<Self> {
-        let attrs = FromRowAttrs::parse(field.attrs)?;
+    pub fn parse(
+        field: Field,
+        container: &ContainerAttrs,
+        lifetime: Option<&syn::Lifetime>,
+    ) -> Result<Self> {
+        let (attrs, bound, tree) = FromRowAttrs::parse(field.attrs, container)?;
 
-        Ok(Self {
+        let mut field = Self {
             ident: field.ident.expect("should be named"),
             ty: field.ty,
             attrs,
-        })
+            bound,
+            tree,
+            rename_all: container.rename_all,
+            lifetime: lifetime.cloned(),
+        };
+
+        if matches!(field.attrs, FromRowAttrs::Columns { .. }) && element_ty(&field.ty).is_none() {
+            return Err(Error::new(
+                field.ty.span(),
+                "`columns` requires an array or a collection with a single type parameter, like `Vec<T>`",
+            ));
+        }
+
+        if let (FromRowAttrs::Columns { names, range_span }, Type::Array(array)) =
+            (&field.attrs, &field.ty)
+        {
+            if let syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Int(len),
+                ..
+            }) = &array.len
+            {
+                if len.base10_parse::<usize>()? != names.len() {
+                    return Err(Error::new(
+                        *range_span,
+                        format!(
+                            "`range` has {} numbers, but the array has a length of {len}",
+                            names.len()
+                        ),
+                    ));
+                }
+            }
+        }
+
+        let collection_ty = field.lifted_ty().unwrap_or(&field.ty);
+        let collection_attr = match &field.attrs {
+            FromRowAttrs::Field {
+                convert: Some(Convert::Split { .. }),
+                ..
+            } => Some("split"),
+            FromRowAttrs::Field {
+                convert: Some(Convert::JsonRows),
+                ..
+            } => Some("json_rows"),
+            _ => None,
+        };
+
+        if let Some(attr) = collection_attr {
+            if element_ty(collection_ty).is_none() {
+                return Err(Error::new(
+                    field.ty.span(),
+                    format!(
+                        "`{attr}` requires a collection with a single type parameter, like `Vec<T>`"
+                    ),
+                ));
+            }
+        }
+
+        if let FromRowAttrs::Flatten {
+            max_depth: Some(_),
+            default,
+            ..
+        } = field.attrs
+        {
+            // `FromRowRef` doesn't pass on the depth, so a borrowed struct would nest forever.
+            if field.borrows(&field.ty) {
+                return Err(Error::new(
+                    field.ty.span(),
+                    "`max_depth` is not supported on a field that borrows from the row",
+                ));
+            }
+
+            if !default && option_inner(&field.ty).is_none() {
+                return Err(Error::new(
+                    field.ty.span(),
+                    "`max_depth` requires an `Option`, which is `None` beyond the maximum depth, or `default`",
+                ));
+            }
+        }
+
+        // Borrowed fields are read using `FromSqlRef`, which doesn't support coercion.
+        if container.coerce && !field.borrows(&field.ty) {
+            if let FromRowAttrs::Field { convert, .. } = &mut field.attrs {
+                convert.get_or_insert(Convert::Coerce);
+            }
+        }
+
+        Ok(field)
+    }
+
+    /// Returns true if `ty` borrows from the row, meaning it mentions the lifetime of the struct.
+    ///
+    /// Borrowed columns are read using `FromSqlRef` and borrowed flattened fields using
+    /// `FromRowRef`.
+    fn borrows(&self, ty: &Type) -> bool {
+        fn mentions(tokens: TokenStream2, lifetime: &syn::Ident) -> bool {
+            let mut tokens = tokens.into_iter().peekable();
+
+            while let Some(token) = tokens.next() {
+                let found = match token {
+                    TokenTree::Punct(punct) if punct.as_char() == '\'' => {
+                        matches!(tokens.peek(), Some(TokenTree::Ident(ident)) if ident == lifetime)
+                    }
+                    TokenTree::Group(group) => mentions(group.stream(), lifetime),
+                    _ => false,
+                };
+
+                if found {
+                    return true;
+                }
+            }
+
+            false
+        }
+
+        match &self.lifetime {
+            Some(lifetime) => mentions(ty.to_token_stream(), &lifetime.ident),
+            None => false,
+        }
+    }
+
+    /// Returns the rust field name with the `rename_all` rule applied.
+    fn renamed_ident(&self) -> String {
+        let ident = self.ident.to_string();
+
+        match self.rename_all {
+            Some(rule) => rule.apply(&ident),
+            None => ident,
+        }
     }
 
     /// Returns a tokenstream of the type that should be returned from either
@@ -137,21 +512,122 @@ impl FromRowField {
                 ..
             } => Some(ty),
             FromRowAttrs::Field {
-                convert: Some(Convert::FromFn(_)),
+                convert:
+                    Some(
+                        Convert::FromFn(_)
+                        | Convert::TryFromFn(_)
+                        | Convert::With(_)
+                        | Convert::FromValue(_)
+                        | Convert::Parse
+                        | Convert::Coerce
+                        | Convert::Codec { .. }
+                        | Convert::Split { .. }
+                        | Convert::JsonRows
+                        | Convert::DateTime { .. },
+                    ),
                 ..
             } => None,
             _ => Some(&self.ty),
         }
     }
 
+    /// Returns the type inside of the `Option` when this field is an `Option` with a conversion,
+    /// which is only applied to non null values.
+    fn lifted_ty(&self) -> Option<&Type> {
+        match &self.attrs {
+            FromRowAttrs::Field {
+                convert: Some(_),
+                lift: true,
+                ..
+            } => option_inner(&self.ty),
+            _ => None,
+        }
+    }
+
     /// Returns the name that maps to the actuall sql column
-    /// By default this is the same as the rust field name but can be overwritten by `#[from_row(rename = "..")]`.
-    fn column_name(&self) -> Cow<str> {
+    /// By default this is the same as the rust field name but can be overwritten by `#[from_row(rename = "..")]`
+    /// or cased using `#[from_row(rename_all = "..")]` on the struct.
+    fn column_name(&self) -> Cow<'_, str> {
         match &self.attrs {
             FromRowAttrs::Field {
                 rename: Some(name), ..
             } => name.as_str().into(),
-            _ => self.ident.to_string().into(),
+            _ => self.renamed_ident().into(),
+        }
+    }
+
+    /// Returns an expression that evaluates to the row index of this field's column.
+    ///
+    /// This is the prefixed column name, unless the column is specified by position using
+    /// `#[from_row(index = ..)]`, in which case it is the position offset by the parent's base
+    /// index.
+    fn row_index(&self) -> TokenStream2 {
+        match &self.attrs {
+            FromRowAttrs::Field {
+                index: Some(index), ..
+            } => quote!(offset + #index),
+            _ => {
+                let column_name = self.column_name();
+                quote!((prefix.unwrap_or("").to_string() + #column_name).as_str())
+            }
+        }
+    }
+
+    /// Returns an expression that evaluates to the prefix passed to a flattened field.
+    fn flatten_prefix(&self) -> TokenStream2 {
+        match self.flatten_segment() {
+            Some(segment) => quote!(Some(&(prefix.unwrap_or("").to_string() + #segment))),
+            None => quote!(prefix),
+        }
+    }
+
+    /// Returns the prefix that a flattened field appends to the prefix of the struct, if any.
+    fn flatten_segment(&self) -> Option<String> {
+        match &self.attrs {
+            FromRowAttrs::Flatten {
+                prefix: Some(Prefix::Value(prefix)),
+                ..
+            } => Some(prefix.clone()),
+            FromRowAttrs::Flatten {
+                prefix: Some(Prefix::Field(separator)),
+                ..
+            } => Some(self.renamed_ident() + separator),
+            _ => None,
+        }
+    }
+
+    /// Returns an expression that is true when a flattened field with `max_depth` is nested too
+    /// deeply to be read.
+    fn too_deep(&self) -> Option<TokenStream2> {
+        let FromRowAttrs::Flatten {
+            max_depth: Some(max_depth),
+            ..
+        } = &self.attrs
+        else {
+            return None;
+        };
+
+        Some(quote!(depth >= #max_depth))
+    }
+
+    /// Returns the depth that is passed to a flattened field, which counts the fields with
+    /// `max_depth` that were flattened.
+    fn flatten_depth(&self) -> TokenStream2 {
+        match &self.attrs {
+            FromRowAttrs::Flatten {
+                max_depth: Some(_), ..
+            } => quote!(depth + 1),
+            _ => quote!(depth),
+        }
+    }
+
+    /// Returns an expression that evaluates to the base index passed to a flattened field.
+    fn flatten_offset(&self) -> TokenStream2 {
+        match &self.attrs {
+            FromRowAttrs::Flatten {
+                index: Some(index), ..
+            } => quote!(offset + #index),
+            _ => quote!(offset),
         }
     }
 
@@ -160,28 +636,43 @@ impl FromRowField {
     /// By default this is `T: rusqlite::types::FromSql`,
     /// when using `flatten` it's: `T: rusqlite_from_row::FromRow`
     /// and when using either `from` or `try_from` attributes it additionally pushes this bound:
-    /// `T: std::convert::From<R>`, where `T` is the type specified in the struct and `R` is the
-    /// type specified in the `[try]_from` attribute.
+    /// `T: std::convert::[Try]From<R>`, where `T` is the type specified in the struct (or the type
+    /// inside of it when the conversion is lifted through an `Option`) and `R` is the type
+    /// specified in the `[try]_from` attribute.
+    ///
+    /// All of these are replaced by the predicates specified in `#[from_row(bound = "..")]`.
     fn add_predicates(&self, predicates: &mut Vec<TokenStream2>) {
+        if let Some(bound) = &self.bound {
+            predicates.extend(bound.iter().map(ToTokens::to_token_stream));
+            return;
+        }
+
         match &self.attrs {
             FromRowAttrs::Field {
                 default, convert, ..
             } => {
                 let target_ty = self.target_ty();
-                let ty = &self.ty;
+                let lifted_ty = self.lifted_ty();
+                let ty = lifted_ty.unwrap_or(&self.ty);
 
                 if let Some(target_ty) = target_ty {
-                    predicates
-                        .push(quote! (#target_ty: rusqlite_from_row::rusqlite::types::FromSql));
+                    if self.borrows(target_ty) {
+                        let lifetime = &self.lifetime;
+                        predicates
+                            .push(quote! (#target_ty: rusqlite_from_row::FromSqlRef<#lifetime>));
+                    } else {
+                        predicates
+                            .push(quote! (#target_ty: rusqlite_from_row::rusqlite::types::FromSql));
+                    }
 
-                    if *default {
+                    if *default && lifted_ty.is_none() {
                         predicates.push(quote! (#target_ty: ::std::default::Default));
                     }
                 }
 
                 match convert {
                     Some(Convert::From(target_ty)) => {
-                        predicates.push(quote!(#target_ty: std::convert::From<#target_ty>))
+                        predicates.push(quote!(#ty: std::convert::From<#target_ty>))
                     }
                     Some(Convert::TryFrom(target_ty)) => {
                         let try_from = quote!(std::convert::TryFrom<#target_ty>);
@@ -190,18 +681,67 @@ impl FromRowField {
                         predicates.push(quote!(rusqlite_from_row::rusqlite::Error: std::convert::From<<#ty as #try_from>::Error>));
                         predicates.push(quote!(<#ty as #try_from>::Error: std::fmt::Debug));
                     }
+                    Some(Convert::Parse) => {
+                        predicates.push(quote!(#ty: std::str::FromStr));
+                        predicates.push(quote!(<#ty as std::str::FromStr>::Err: std::fmt::Display));
+                    }
+                    Some(Convert::Coerce) => {
+                        predicates.push(quote!(#ty: rusqlite_from_row::rusqlite::types::FromSql));
+                    }
+                    Some(Convert::Codec { .. }) => {
+                        predicates.push(quote!(#ty: rusqlite_from_row::codec::DeserializeOwned));
+                    }
+                    Some(Convert::Split { .. }) => {
+                        let element = element_ty(ty).expect("validated when parsing");
+
+                        predicates.push(quote!(#ty: std::iter::FromIterator<#element>));
+                        predicates.push(quote!(#element: std::str::FromStr));
+                        predicates
+                            .push(quote!(<#element as std::str::FromStr>::Err: std::fmt::Display));
+                    }
+                    Some(Convert::JsonRows) => {
+                        let element = element_ty(ty).expect("validated when parsing");
+
+                        predicates.push(quote!(#ty: std::iter::FromIterator<#element>));
+                        predicates
+                            .push(quote!(#element: rusqlite_from_row::json::DeserializeOwned));
+                    }
+                    Some(Convert::DateTime { .. }) => {
+                        predicates.push(quote!(#ty: rusqlite_from_row::datetime::FromSqlDateTime));
+                    }
                     _ => {}
                 }
             }
+            FromRowAttrs::Flatten {
+                max_depth: Some(_), ..
+            } => {
+                // A recursive field would require the impl to prove its own where clause, which
+                // overflows, generic recursive structs can use `bound` instead.
+            }
             FromRowAttrs::Flatten { default, .. } => {
                 let ty = &self.ty;
 
-                predicates.push(quote! (#ty: rusqlite_from_row::FromRow));
+                if self.borrows(ty) {
+                    let lifetime = &self.lifetime;
+                    predicates.push(quote! (#ty: rusqlite_from_row::FromRowRef<#lifetime>));
+                } else {
+                    predicates.push(quote! (#ty: rusqlite_from_row::FromRow));
+                }
 
                 if *default {
                     predicates.push(quote! (#ty: ::std::default::Default));
                 }
             }
+            FromRowAttrs::Columns { .. } => {
+                let ty = &self.ty;
+                let element = element_ty(ty).expect("validated when parsing");
+
+                predicates.push(quote! (#element: rusqlite_from_row::rusqlite::types::FromSql));
+
+                if !matches!(ty, Type::Array(_)) {
+                    predicates.push(quote! (#ty: std::iter::FromIterator<#element>));
+                }
+            }
             FromRowAttrs::Skip => {
                 let ty = &self.ty;
 
@@ -212,61 +752,112 @@ impl FromRowField {
 
     fn generate_is_all_null(&self) -> Option<TokenStream2> {
         let is_all_null = match &self.attrs {
-            FromRowAttrs::Flatten { prefix, .. } => {
+            FromRowAttrs::Flatten { .. } => {
                 let ty = &self.ty;
 
-                let prefix = match &prefix {
-                    Some(Prefix::Value(prefix)) => {
-                        quote!(Some(&(prefix.unwrap_or("").to_string() + #prefix)))
-                    }
-                    Some(Prefix::Field) => {
-                        let ident_str = format!("{}_", self.ident);
-                        quote!(Some(&(prefix.unwrap_or("").to_string() + #ident_str)))
-                    }
-                    None => quote!(prefix),
-                };
+                let prefix = self.flatten_prefix();
 
-                quote!(<#ty as rusqlite_from_row::FromRow>::is_all_null(row, #prefix)?)
+                let offset = self.flatten_offset();
+
+                if self.borrows(ty) {
+                    let lifetime = &self.lifetime;
+                    quote!(<#ty as rusqlite_from_row::FromRowRef<#lifetime>>::is_all_null_ref(row, #prefix, #offset)?)
+                } else {
+                    let depth = self.flatten_depth();
+
+                    quote!(<#ty as rusqlite_from_row::FromRow>::is_all_null_depth(row, #prefix, #offset, #depth)?)
+                }
             }
             FromRowAttrs::Field { .. } => {
-                let column_name = self.column_name();
+                let row_index = self.row_index();
 
                 quote! {
-                    rusqlite_from_row::rusqlite::Row::get_ref::<&str>(
+                    rusqlite_from_row::rusqlite::Row::get_ref(
                         row,
-                        &(prefix.unwrap_or("").to_string() + #column_name)
+                        #row_index
                     )? == rusqlite_from_row::rusqlite::types::ValueRef::Null
                 }
             }
+            FromRowAttrs::Columns { names, .. } => {
+                let is_null = names.iter().map(|name| {
+                    quote! {
+                        rusqlite_from_row::rusqlite::Row::get_ref(
+                            row,
+                            (prefix.unwrap_or("").to_string() + #name).as_str()
+                        )? == rusqlite_from_row::rusqlite::types::ValueRef::Null
+                    }
+                });
+
+                quote!((#(#is_null)&&*))
+            }
             FromRowAttrs::Skip => return None,
         };
 
+        if let Some(too_deep) = self.too_deep() {
+            return Some(quote!((#too_deep || #is_all_null)));
+        }
+
         Some(is_all_null)
     }
 
+    /// Wraps the expression that converts a non null column, such that an SQL `null` produces
+    /// `None` for a lifted `Option` field, or the default value when using `default`.
+    fn guard_null(&self, base: TokenStream2) -> TokenStream2 {
+        let field_ty = &self.ty;
+        let row_index = self.row_index();
+
+        let null = if self.lifted_ty().is_some() {
+            quote!(None)
+        } else if matches!(self.attrs, FromRowAttrs::Field { default: true, .. }) {
+            quote!(<#field_ty as ::std::default::Default>::default())
+        } else {
+            return base;
+        };
+
+        let base = match self.lifted_ty() {
+            Some(_) => quote!(Some(#base)),
+            None => base,
+        };
+
+        quote! {
+            match rusqlite_from_row::rusqlite::Row::get_ref(row, #row_index)? {
+                rusqlite_from_row::rusqlite::types::ValueRef::Null => #null,
+                _ => #base,
+            }
+        }
+    }
+
     /// Generate the line needed to retrieve this field from a row when calling `try_from_row`.
     fn generate_try_from_row(&self) -> TokenStream2 {
         let ident = &self.ident;
-        let column_name = self.column_name();
         let field_ty = &self.ty;
 
         let base = match &self.attrs {
-            FromRowAttrs::Flatten { prefix, default } => {
+            FromRowAttrs::Flatten { default, .. } => {
                 let ty = &self.ty;
 
-                let prefix = match &prefix {
-                    Some(Prefix::Value(prefix)) => {
-                        quote!(Some(&(prefix.unwrap_or("").to_string() + #prefix)))
-                    }
-                    Some(Prefix::Field) => {
-                        let ident_str = format!("{}_", self.ident);
-                        quote!(Some(&(prefix.unwrap_or("").to_string() + #ident_str)))
-                    }
-                    None => quote!(prefix),
-                };
+                let prefix = self.flatten_prefix();
 
-                if *default {
-                    let value = quote!(<std::option::Option<#ty> as rusqlite_from_row::FromRow>::try_from_row_prefixed(row, #prefix)?);
+                let offset = self.flatten_offset();
+
+                if self.borrows(ty) {
+                    let lifetime = &self.lifetime;
+
+                    if *default {
+                        let value = quote!(<std::option::Option<#ty> as rusqlite_from_row::FromRowRef<#lifetime>>::try_from_row_ref_prefixed(row, #prefix, #offset)?);
+
+                        quote! {
+                            match #value {
+                                Some(value) => value,
+                                None => <#ty as ::std::default::Default>::default(),
+                            }
+                        }
+                    } else {
+                        quote!(<#ty as rusqlite_from_row::FromRowRef<#lifetime>>::try_from_row_ref_prefixed(row, #prefix, #offset)?)
+                    }
+                } else if *default {
+                    let depth = self.flatten_depth();
+                    let value = quote!(<std::option::Option<#ty> as rusqlite_from_row::FromRow>::try_from_row_depth(row, #prefix, #offset, #depth)?);
 
                     quote! {
                         match #value {
@@ -275,42 +866,201 @@ impl FromRowField {
                         }
                     }
                 } else {
-                    quote!(<#ty as rusqlite_from_row::FromRow>::try_from_row_prefixed(row, #prefix)?)
+                    let depth = self.flatten_depth();
+
+                    quote!(<#ty as rusqlite_from_row::FromRow>::try_from_row_depth(row, #prefix, #offset, #depth)?)
                 }
             }
+            FromRowAttrs::Field {
+                convert: Some(Convert::TryFromFn(func)),
+                ..
+            } => {
+                let row_index = self.row_index();
+
+                self.guard_null(quote!(rusqlite_from_row::get_try_from_fn(row, #row_index, #func)?))
+            }
+            FromRowAttrs::Field {
+                convert: Some(Convert::Parse),
+                ..
+            } => {
+                let row_index = self.row_index();
+                let ty = self.lifted_ty().unwrap_or(field_ty);
+
+                self.guard_null(quote!(rusqlite_from_row::get_parsed::<#ty, _>(row, #row_index)?))
+            }
+            FromRowAttrs::Field {
+                convert: Some(Convert::Coerce),
+                ..
+            } => {
+                let row_index = self.row_index();
+                let ty = self.lifted_ty().unwrap_or(field_ty);
+
+                self.guard_null(quote!(rusqlite_from_row::get_coerced::<#ty, _>(row, #row_index)?))
+            }
+            FromRowAttrs::Field {
+                convert: Some(Convert::Codec { codec, compress }),
+                ..
+            } => {
+                let row_index = self.row_index();
+                let ty = self.lifted_ty().unwrap_or(field_ty);
+
+                self.guard_null(quote! {
+                    rusqlite_from_row::codec::get_decoded::<#ty, _>(
+                        row,
+                        #row_index,
+                        rusqlite_from_row::codec::Codec::#codec,
+                        rusqlite_from_row::codec::Compression::#compress,
+                    )?
+                })
+            }
+            FromRowAttrs::Field {
+                convert: Some(Convert::Split { separator, trim }),
+                ..
+            } => {
+                let row_index = self.row_index();
+                let ty = self.lifted_ty().unwrap_or(field_ty);
+
+                self.guard_null(quote! {
+                    rusqlite_from_row::get_split::<#ty, _, _>(row, #row_index, #separator, #trim)?
+                })
+            }
+            FromRowAttrs::Field {
+                convert: Some(Convert::JsonRows),
+                ..
+            } => {
+                let row_index = self.row_index();
+                let ty = self.lifted_ty().unwrap_or(field_ty);
+
+                self.guard_null(
+                    quote!(rusqlite_from_row::json::get_json_rows::<#ty, _, _>(row, #row_index)?),
+                )
+            }
+            FromRowAttrs::Field {
+                convert: Some(Convert::DateTime { unit, zone }),
+                ..
+            } => {
+                let row_index = self.row_index();
+                let ty = self.lifted_ty().unwrap_or(field_ty);
+
+                let options = quote! {
+                    rusqlite_from_row::datetime::DateTimeOptions::new(
+                        rusqlite_from_row::datetime::EpochUnit::#unit,
+                        rusqlite_from_row::datetime::Zone::#zone,
+                    )
+                };
+
+                self.guard_null(quote! {
+                    rusqlite_from_row::get_with(row, #row_index, |value| {
+                        <#ty as rusqlite_from_row::datetime::FromSqlDateTime>::from_sql_datetime(value, &#options)
+                    })?
+                })
+            }
+            FromRowAttrs::Field {
+                convert: Some(Convert::FromValue(func)),
+                ..
+            } => {
+                let row_index = self.row_index();
+
+                self.guard_null(quote!(rusqlite_from_row::get_from_value(row, #row_index, #func)?))
+            }
+            FromRowAttrs::Field {
+                convert: Some(Convert::With(module)),
+                default,
+                ..
+            } => {
+                let row_index = self.row_index();
+
+                let convert = if self.lifted_ty().is_some() {
+                    quote! {
+                        |value| match value {
+                            rusqlite_from_row::rusqlite::types::ValueRef::Null => Ok(None),
+                            value => #module::from_sql(value).map(Some),
+                        }
+                    }
+                } else if *default {
+                    quote! {
+                        |value| match value {
+                            rusqlite_from_row::rusqlite::types::ValueRef::Null => Ok(<#field_ty as ::std::default::Default>::default()),
+                            value => #module::from_sql(value),
+                        }
+                    }
+                } else {
+                    quote!(#module::from_sql)
+                };
+
+                quote!(rusqlite_from_row::get_with(row, #row_index, #convert)?)
+            }
             FromRowAttrs::Field {
                 convert, default, ..
             } => {
-                let column_name = quote!(&(prefix.unwrap_or("").to_string() + #column_name));
+                let row_index = self.row_index();
                 let target_ty = self
                     .target_ty()
                     .cloned()
                     .unwrap_or_else(|| parse_str("_").unwrap());
+                let lifted_ty = self.lifted_ty();
+                let convert_ty = lifted_ty.unwrap_or(field_ty);
+                let read_ty = match lifted_ty {
+                    Some(_) => parse_quote!(std::option::Option<#target_ty>),
+                    None => target_ty.clone(),
+                };
 
-                let base = if *default {
+                let base = if self.borrows(&read_ty) {
+                    if *default {
+                        quote! {
+                            match rusqlite_from_row::get_ref::<std::option::Option<#read_ty>, _>(row, #row_index)? {
+                                Some(value) => value,
+                                None => <#read_ty as ::std::default::Default>::default(),
+                            }
+                        }
+                    } else {
+                        quote!(rusqlite_from_row::get_ref::<#read_ty, _>(row, #row_index)?)
+                    }
+                } else if *default {
                     quote! {
-                        match rusqlite_from_row::rusqlite::Row::get_ref::<&str>(row, #column_name)? {
-                            ::rusqlite::types::ValueRef::Null => <#target_ty as ::std::default::Default>::default(),
-                            value => <#target_ty as ::rusqlite::types::FromSql>::column_result(value)?,
+                        match rusqlite_from_row::rusqlite::Row::get_ref(row, #row_index)? {
+                            rusqlite_from_row::rusqlite::types::ValueRef::Null => <#read_ty as ::std::default::Default>::default(),
+                            value => <#read_ty as rusqlite_from_row::rusqlite::types::FromSql>::column_result(value)?,
                         }
                     }
                 } else {
-                    quote!(rusqlite_from_row::rusqlite::Row::get::<&str, #target_ty>(row, #column_name)?)
+                    quote!(rusqlite_from_row::rusqlite::Row::get::<_, #read_ty>(row, #row_index)?)
                 };
 
-                match convert {
-                    Some(Convert::From(_)) => {
-                        quote!(<#field_ty as std::convert::From<#target_ty>>::from(#base))
+                match (convert, lifted_ty) {
+                    (Some(Convert::From(_)), None) => {
+                        quote!(<#convert_ty as std::convert::From<#target_ty>>::from(#base))
+                    }
+                    (Some(Convert::From(_)), Some(_)) => {
+                        quote!(#base.map(<#convert_ty as std::convert::From<#target_ty>>::from))
                     }
-                    Some(Convert::TryFrom(_)) => {
-                        quote!(<#field_ty as std::convert::TryFrom<#target_ty>>::try_from(#base)?)
+                    (Some(Convert::TryFrom(_)), None) => {
+                        quote!(<#convert_ty as std::convert::TryFrom<#target_ty>>::try_from(#base)?)
                     }
-                    Some(Convert::FromFn(func)) => {
+                    (Some(Convert::TryFrom(_)), Some(_)) => {
+                        quote!(#base.map(<#convert_ty as std::convert::TryFrom<#target_ty>>::try_from).transpose()?)
+                    }
+                    (Some(Convert::FromFn(func)), None) => {
                         quote!(#func(#base))
                     }
+                    (Some(Convert::FromFn(func)), Some(_)) => {
+                        quote!(#base.map(#func))
+                    }
                     _ => base,
                 }
             }
+            FromRowAttrs::Columns { names, .. } => {
+                let element = element_ty(field_ty).expect("validated when parsing");
+                let values = names.iter().map(|name| {
+                    quote!(rusqlite_from_row::rusqlite::Row::get::<_, #element>(row, (prefix.unwrap_or("").to_string() + #name).as_str())?)
+                });
+
+                if let Type::Array(_) = field_ty {
+                    quote!([#(#values),*])
+                } else {
+                    quote!(<#field_ty as std::iter::FromIterator<#element>>::from_iter([#(#values),*]))
+                }
+            }
             FromRowAttrs::Skip => {
                 let ty = &self.ty;
 
@@ -318,19 +1068,43 @@ impl FromRowField {
             }
         };
 
+        if let Some(too_deep) = self.too_deep() {
+            return quote! {
+                #ident: if #too_deep {
+                    <#field_ty as ::std::default::Default>::default()
+                } else {
+                    #base
+                }
+            };
+        }
+
         quote!(#ident: #base)
     }
 }
 
+#[allow(clippy::large_enum_variant)]
 enum FromRowAttrs {
     Flatten {
         prefix: Option<Prefix>,
+        index: Option<usize>,
         default: bool,
+        /// The number of times the prefix may be nested, for recursive structs.
+        max_depth: Option<usize>,
     },
     Field {
         rename: Option<String>,
+        index: Option<usize>,
         convert: Option<Convert>,
         default: bool,
+        /// Whether a conversion of an `Option` field is applied to the value inside of it, which
+        /// is disabled by `no_lift`.
+        lift: bool,
+    },
+    /// A sequence of columns, named by substituting each number of a range in a pattern.
+    Columns {
+        names: Vec<String>,
+        /// The span of the `range` attribute, used to report a count that doesn't match an array.
+        range_span: proc_macro2::Span,
     },
     Skip,
 }
@@ -339,31 +1113,160 @@ enum Convert {
     From(Type),
     TryFrom(Type),
     FromFn(ExprPath),
+    TryFromFn(ExprPath),
+    /// A module providing `from_sql(ValueRef) -> FromSqlResult<T>`.
+    With(ExprPath),
+    /// A function receiving `(ValueRef, &ColumnContext)` and returning `FromSqlResult<T>`.
+    FromValue(ExprPath),
+    /// Parses a text column using `FromStr`.
+    Parse,
+    /// Reads a column using `FromSql`, after applying SQLite-like coercions when needed.
+    Coerce,
+    /// Decodes a `BLOB` using the variant names of `Codec` and `Compression`.
+    Codec {
+        codec: Ident,
+        compress: Ident,
+    },
+    /// Splits a text column and parses each element using `FromStr`, optionally trimming them.
+    Split {
+        separator: String,
+        trim: bool,
+    },
+    /// Deserializes each element of a JSON array using `serde_json`.
+    JsonRows,
+    /// Decodes a datetime stored as `TEXT`, `REAL` or `INTEGER` using `FromSqlDateTime`, with
+    /// the variant names of `EpochUnit` and `Zone`.
+    DateTime {
+        unit: Ident,
+        zone: Ident,
+    },
+}
+
+/// The role of a field in a struct that implements `TreeNode`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TreeRole {
+    Key,
+    ParentKey,
+    Children,
+}
+
+impl TreeRole {
+    fn from_path(path: &syn::Path) -> Option<Self> {
+        if path.is_ident("key") {
+            Some(TreeRole::Key)
+        } else if path.is_ident("parent_key") {
+            Some(TreeRole::ParentKey)
+        } else if path.is_ident("children") {
+            Some(TreeRole::Children)
+        } else {
+            None
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            TreeRole::Key => "key",
+            TreeRole::ParentKey => "parent_key",
+            TreeRole::Children => "children",
+        }
+    }
 }
 
 enum Prefix {
     Value(String),
-    Field,
+    /// The field name followed by a separator.
+    Field(String),
+}
+
+/// Attributes specified on the struct itself.
+#[derive(Default)]
+struct ContainerAttrs {
+    rename_all: Option<RenameRule>,
+    prefix_separator: Option<String>,
+    after: Option<ExprPath>,
+    validate: Option<ExprPath>,
+    bound: Option<Vec<WherePredicate>>,
+    /// Applies `coerce` to all fields without another conversion.
+    coerce: bool,
+    /// The span of `borrow`, which implements `FromRowRef` for the lifetime of the struct.
+    borrow: Option<proc_macro2::Span>,
+}
+
+impl ContainerAttrs {
+    fn parse(attrs: Vec<Attribute>) -> Result<Self> {
+        let mut container = Self::default();
+
+        for attr in attrs {
+            if !attr.meta.path().is_ident("from_row") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    let rule: LitStr = meta.value()?.parse()?;
+                    container.rename_all = Some(RenameRule::parse(&rule)?);
+                } else if meta.path.is_ident("prefix_separator") {
+                    let separator: LitStr = meta.value()?.parse()?;
+                    container.prefix_separator = Some(separator.value());
+                } else if meta.path.is_ident("after") {
+                    let after: LitStr = meta.value()?.parse()?;
+                    container.after = Some(parse_str(&after.value())?);
+                } else if meta.path.is_ident("validate") {
+                    let validate: LitStr = meta.value()?.parse()?;
+                    container.validate = Some(parse_str(&validate.value())?);
+                } else if meta.path.is_ident("bound") {
+                    container.bound = Some(parse_bound(meta.value()?.parse()?)?);
+                } else if meta.path.is_ident("coerce") {
+                    container.coerce = true;
+                } else if meta.path.is_ident("borrow") {
+                    container.borrow = Some(meta.path.span());
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(container)
+    }
 }
 
 impl FromRowAttrs {
-    fn parse(attrs: Vec<Attribute>) -> Result<FromRowAttrs> {
+    /// Parses the attributes of a field, together with the `bound` attribute which is valid for
+    /// any kind of field and the role of the field in a tree.
+    fn parse(
+        attrs: Vec<Attribute>,
+        container: &ContainerAttrs,
+    ) -> Result<(FromRowAttrs, Option<Vec<WherePredicate>>, Option<TreeRole>)> {
         let Some(span) = attrs.first().map(|attr| attr.span()) else {
-            return Ok(Self::Field {
+            let attrs = Self::Field {
                 rename: None,
+                index: None,
                 convert: None,
                 default: false,
-            });
+                lift: true,
+            };
+
+            return Ok((attrs, None, None));
         };
 
         let mut flatten = false;
         let mut prefix = None;
-        let mut try_from = None;
-        let mut from = None;
-        let mut from_fn = None;
+        let mut prefix_separator = None;
+        let mut converts = Vec::new();
         let mut rename = None;
+        let mut index = None;
         let mut skip = false;
         let mut default = false;
+        let mut bound = None;
+        let mut codec = None;
+        let mut compress = None;
+        let mut columns = None;
+        let mut range = None;
+        let mut split = None;
+        let mut trim = false;
+        let mut tree = None;
+        let mut max_depth = None;
+        let mut no_lift = false;
 
         for attr in attrs {
             if !attr.meta.path().is_ident("from_row") {
@@ -375,24 +1278,94 @@ impl FromRowAttrs {
                     flatten = true;
                 } else if meta.path.is_ident("prefix") {
                     let prefix_value = if let Ok(value) = meta.value() {
-                        Prefix::Value(value.parse::<LitStr>()?.value())
+                        Some(value.parse::<LitStr>()?.value())
                     } else {
-                        Prefix::Field
+                        None
                     };
 
                     prefix = Some(prefix_value);
+                } else if meta.path.is_ident("prefix_separator") {
+                    let separator: LitStr = meta.value()?.parse()?;
+                    prefix_separator = Some(separator.value());
                 } else if meta.path.is_ident("try_from") {
                     let try_from_str: LitStr = meta.value()?.parse()?;
-                    try_from = Some(parse_str(&try_from_str.value())?);
+                    converts.push(Convert::TryFrom(parse_str(&try_from_str.value())?));
                 } else if meta.path.is_ident("from") {
                     let from_str: LitStr = meta.value()?.parse()?;
-                    from = Some(parse_str(&from_str.value())?);
+                    converts.push(Convert::From(parse_str(&from_str.value())?));
                 } else if meta.path.is_ident("from_fn") {
                     let from_fn_str: LitStr = meta.value()?.parse()?;
-                    from_fn = Some(parse_str(&from_fn_str.value())?);
+                    converts.push(Convert::FromFn(parse_str(&from_fn_str.value())?));
+                } else if meta.path.is_ident("try_from_fn") {
+                    let try_from_fn_str: LitStr = meta.value()?.parse()?;
+                    converts.push(Convert::TryFromFn(parse_str(&try_from_fn_str.value())?));
+                } else if meta.path.is_ident("from_value") {
+                    let from_value_str: LitStr = meta.value()?.parse()?;
+                    converts.push(Convert::FromValue(parse_str(&from_value_str.value())?));
+                } else if meta.path.is_ident("parse") {
+                    converts.push(Convert::Parse);
+                } else if meta.path.is_ident("coerce") {
+                    converts.push(Convert::Coerce);
+                } else if meta.path.is_ident("codec") {
+                    const CODECS: &[(&str, &str)] = &[
+                        ("cbor", "Cbor"),
+                        ("msgpack", "MessagePack"),
+                        ("bincode", "Bincode"),
+                        ("postcard", "Postcard"),
+                    ];
+
+                    codec = Some(parse_variant(meta.value()?.parse()?, "codec", CODECS)?);
+                } else if meta.path.is_ident("compress") {
+                    const COMPRESSIONS: &[(&str, &str)] =
+                        &[("none", "None"), ("zstd", "Zstd"), ("deflate", "Deflate")];
+
+                    compress = Some(parse_variant(
+                        meta.value()?.parse()?,
+                        "compress",
+                        COMPRESSIONS,
+                    )?);
+                } else if meta.path.is_ident("columns") {
+                    columns = Some(meta.value()?.parse::<LitStr>()?);
+                } else if meta.path.is_ident("range") {
+                    let range_str: LitStr = meta.value()?.parse()?;
+                    range = Some((range_str.span(), parse_range(range_str)?));
+                } else if meta.path.is_ident("split") {
+                    let separator: LitStr = meta.value()?.parse()?;
+
+                    if separator.value().is_empty() {
+                        return Err(Error::new(
+                            separator.span(),
+                            "`split` separator should not be empty",
+                        ));
+                    }
+
+                    split = Some(separator.value());
+                } else if meta.path.is_ident("trim") {
+                    trim = true;
+                } else if meta.path.is_ident("json_rows") {
+                    converts.push(Convert::JsonRows);
+                } else if let Some(role) = TreeRole::from_path(&meta.path) {
+                    if tree.replace(role).is_some() {
+                        return Err(meta.error("can't combine `key`, `parent_key` or `children`"));
+                    }
+                } else if meta.path.is_ident("max_depth") {
+                    let max_depth_lit: LitInt = meta.value()?.parse()?;
+                    max_depth = Some(max_depth_lit.base10_parse()?);
+                } else if meta.path.is_ident("datetime") {
+                    converts.push(parse_datetime(&meta)?);
+                } else if meta.path.is_ident("with") {
+                    let with_str: LitStr = meta.value()?.parse()?;
+                    converts.push(Convert::With(parse_str(&with_str.value())?));
                 } else if meta.path.is_ident("rename") {
                     let rename_str: LitStr = meta.value()?.parse()?;
                     rename = Some(rename_str.value());
+                } else if meta.path.is_ident("index") {
+                    let index_lit: LitInt = meta.value()?.parse()?;
+                    index = Some(index_lit.base10_parse()?);
+                } else if meta.path.is_ident("bound") {
+                    bound = Some(parse_bound(meta.value()?.parse()?)?);
+                } else if meta.path.is_ident("no_lift") {
+                    no_lift = true;
                 } else if meta.path.is_ident("skip") {
                     skip = true;
                 } else if meta.path.is_ident("default") {
@@ -403,14 +1376,76 @@ impl FromRowAttrs {
             })?;
         }
 
+        match (codec, compress) {
+            (Some(codec), compress) => converts.push(Convert::Codec {
+                compress: compress.unwrap_or_else(|| Ident::new("None", codec.span())),
+                codec,
+            }),
+            (None, Some(_)) => {
+                return Err(Error::new(
+                    span,
+                    "`compress` is only valid in combination with `codec`",
+                ));
+            }
+            (None, None) => {}
+        }
+
+        match split {
+            Some(separator) => converts.push(Convert::Split { separator, trim }),
+            None if trim => {
+                return Err(Error::new(
+                    span,
+                    "`trim` is only valid in combination with `split`",
+                ));
+            }
+            None => {}
+        }
+
+        let columns = match (columns, range) {
+            (Some(pattern), Some((range_span, range))) => {
+                if !pattern.value().contains("{}") {
+                    return Err(Error::new(
+                        pattern.span(),
+                        "`columns` pattern should contain `{}`, which is replaced by each number of the `range`",
+                    ));
+                }
+
+                Some((
+                    range
+                        .map(|number| pattern.value().replace("{}", &number.to_string()))
+                        .collect(),
+                    range_span,
+                ))
+            }
+            (None, None) => None,
+            _ => {
+                return Err(Error::new(span, "`columns` and `range` should be combined"));
+            }
+        };
+
+        // Children are assembled by `build_tree`, not read from the row.
+        if tree == Some(TreeRole::Children) {
+            if skip {
+                return Err(Error::new(
+                    span,
+                    "can't combine `children` with other attributes",
+                ));
+            }
+
+            skip = true;
+        }
+
         let attrs = if skip {
             let other_attrs = flatten
                 || default
                 || prefix.is_some()
-                || try_from.is_some()
-                || from_fn.is_some()
-                || from.is_some()
-                || rename.is_some();
+                || prefix_separator.is_some()
+                || !converts.is_empty()
+                || rename.is_some()
+                || index.is_some()
+                || columns.is_some()
+                || max_depth.is_some()
+                || no_lift;
 
             if other_attrs {
                 return Err(Error::new(
@@ -420,43 +1455,245 @@ impl FromRowAttrs {
             }
 
             Self::Skip
-        } else if flatten {
-            if rename.is_some() || from.is_some() || try_from.is_some() || from_fn.is_some() {
+        } else if let Some((names, range_span)) = columns {
+            if flatten
+                || max_depth.is_some()
+                || default
+                || rename.is_some()
+                || index.is_some()
+                || !converts.is_empty()
+                || no_lift
+            {
                 return Err(Error::new(
                     span,
-                    "can't combine `skip` with other attributes",
+                    "can't combine `columns` with other attributes",
                 ));
             }
 
-            Self::Flatten { default, prefix }
-        } else {
-            if prefix.is_some() {
+            Self::Columns { names, range_span }
+        } else if flatten {
+            if rename.is_some() || !converts.is_empty() || no_lift {
                 return Err(Error::new(
                     span,
-                    "`prefix` attribute is only valid in combination with `flatten`",
+                    "can't combine `skip` with other attributes",
                 ));
             }
 
-            let convert = match (try_from, from, from_fn) {
-                (Some(try_from), None, None) => Some(Convert::TryFrom(try_from)),
-                (None, Some(from), None) => Some(Convert::From(from)),
-                (None, None, Some(from_fn)) => Some(Convert::FromFn(from_fn)),
-                (None, None, None) => None,
-                _ => {
+            let prefix = match (prefix, prefix_separator) {
+                (Some(Some(_)), Some(_)) => {
                     return Err(Error::new(
                         span,
-                        "can't combine `try_from`, `from` or `from_fn`",
-                    ))
+                        "`prefix_separator` is only valid in combination with a `prefix` without a value",
+                    ));
                 }
+                (Some(Some(value)), None) => Some(Prefix::Value(value)),
+                (Some(None), separator) => Some(Prefix::Field(
+                    separator
+                        .or_else(|| container.prefix_separator.clone())
+                        .unwrap_or_else(|| "_".to_string()),
+                )),
+                (None, Some(_)) => {
+                    return Err(Error::new(
+                        span,
+                        "`prefix_separator` is only valid in combination with `prefix`",
+                    ));
+                }
+                (None, None) => None,
             };
 
+            if max_depth.is_some() && prefix.is_none() {
+                return Err(Error::new(
+                    span,
+                    "`max_depth` is only valid in combination with `prefix`",
+                ));
+            }
+
+            Self::Flatten {
+                default,
+                prefix,
+                index,
+                max_depth,
+            }
+        } else {
+            if max_depth.is_some() {
+                return Err(Error::new(
+                    span,
+                    "`max_depth` is only valid in combination with `flatten`",
+                ));
+            }
+
+            if prefix.is_some() || prefix_separator.is_some() {
+                return Err(Error::new(
+                    span,
+                    "`prefix` and `prefix_separator` attributes are only valid in combination with `flatten`",
+                ));
+            }
+
+            if rename.is_some() && index.is_some() {
+                return Err(Error::new(span, "can't combine `rename` with `index`"));
+            }
+
+            if converts.len() > 1 {
+                return Err(Error::new(
+                    span,
+                    "can't combine `try_from`, `from`, `from_fn`, `try_from_fn`, `with`, `from_value`, `parse`, `coerce`, `codec`, `split`, `json_rows` or `datetime`",
+                ));
+            }
+
+            let convert = converts.pop();
+
+            if no_lift && convert.is_none() {
+                return Err(Error::new(
+                    span,
+                    "`no_lift` is only valid in combination with a conversion",
+                ));
+            }
+
             Self::Field {
                 rename,
+                index,
                 convert,
                 default,
+                lift: !no_lift,
             }
         };
 
-        Ok(attrs)
+        if matches!(tree, Some(TreeRole::Key | TreeRole::ParentKey))
+            && !matches!(attrs, Self::Field { .. })
+        {
+            return Err(Error::new(
+                span,
+                "`key` and `parent_key` are only valid on fields that are read from a column",
+            ));
+        }
+
+        Ok((attrs, bound, tree))
+    }
+}
+
+/// Returns the type inside of an `Option`, if `ty` is written as one.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+
+    let segment = path.path.segments.last()?;
+
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    single_type_argument(segment)
+}
+
+/// Returns the element type of an array, or of a collection with a single type parameter like
+/// `Vec<T>`.
+fn element_ty(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Array(array) => Some(&array.elem),
+        Type::Path(path) => single_type_argument(path.path.segments.last()?),
+        _ => None,
     }
 }
+
+fn single_type_argument(segment: &PathSegment) -> Option<&Type> {
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => {
+            match arguments.args.first()? {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Maps the string value of the attribute `name` to the name of an enum variant, using a list
+/// of `(value, variant)` pairs.
+fn parse_variant(lit: LitStr, name: &str, options: &[(&str, &str)]) -> Result<Ident> {
+    let value = lit.value();
+
+    options
+        .iter()
+        .find(|(option, _)| *option == value)
+        .map(|(_, variant)| Ident::new(variant, lit.span()))
+        .ok_or_else(|| {
+            let options = options
+                .iter()
+                .map(|(option, _)| format!("`{option}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            Error::new(
+                lit.span(),
+                format!("unknown `{name}`, expected one of {options}"),
+            )
+        })
+}
+
+/// Parses `datetime`, optionally followed by `(unit = "..", timezone = "..")`.
+fn parse_datetime(meta: &ParseNestedMeta) -> Result<Convert> {
+    const UNITS: &[(&str, &str)] = &[
+        ("seconds", "Seconds"),
+        ("millis", "Millis"),
+        ("micros", "Micros"),
+        ("nanos", "Nanos"),
+    ];
+    const ZONES: &[(&str, &str)] = &[("utc", "Utc"), ("local", "Local")];
+
+    let mut unit = Ident::new("Seconds", meta.path.span());
+    let mut zone = Ident::new("Utc", meta.path.span());
+
+    if meta.input.peek(token::Paren) {
+        meta.parse_nested_meta(|meta| {
+            if meta.path.is_ident("unit") {
+                unit = parse_variant(meta.value()?.parse()?, "unit", UNITS)?;
+            } else if meta.path.is_ident("timezone") {
+                zone = parse_variant(meta.value()?.parse()?, "timezone", ZONES)?;
+            } else {
+                return Err(meta.error("expected `unit` or `timezone`"));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(Convert::DateTime { unit, zone })
+}
+
+/// Parses an integer range like `1..=12` or `0..4`, specified in a `range` attribute.
+fn parse_range(range: LitStr) -> Result<RangeInclusive<i64>> {
+    let error = || {
+        Error::new(
+            range.span(),
+            "expected a range of integers like `1..=12` or `0..4`",
+        )
+    };
+
+    let value = range.value();
+    let (start, end, inclusive) = match value.split_once("..=") {
+        Some((start, end)) => (start, end, true),
+        None => {
+            let (start, end) = value.split_once("..").ok_or_else(error)?;
+            (start, end, false)
+        }
+    };
+
+    let start: i64 = start.trim().parse().map_err(|_| error())?;
+    let end: i64 = end.trim().parse().map_err(|_| error())?;
+    let end = if inclusive { end } else { end - 1 };
+
+    if start > end {
+        return Err(Error::new(range.span(), "`range` should not be empty"));
+    }
+
+    Ok(start..=end)
+}
+
+/// Parses the where clause predicates specified in a `bound` attribute.
+fn parse_bound(bound: LitStr) -> Result<Vec<WherePredicate>> {
+    let predicates =
+        Punctuated::<WherePredicate, Token![,]>::parse_terminated.parse_str(&bound.value())?;
+
+    Ok(predicates.into_iter().collect())
+}
@@ -0,0 +1,603 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    ext::IdentExt, spanned::Spanned, Attribute, Data, DataStruct, DeriveInput, Error, ExprPath,
+    Fields, Ident, LitStr, Result, Type, Visibility,
+};
+
+use crate::{
+    option_inner, single_type_argument, ContainerAttrs, Convert, FromRowAttrs, FromRowField,
+};
+
+/// Main struct for deriving `Table` for a struct.
+pub struct DeriveTable {
+    ident: syn::Ident,
+    vis: Visibility,
+    generics: syn::Generics,
+    /// The name of the table from `#[table(name = "..")]`.
+    name: String,
+    /// The name of the struct generated by `#[table(patch = "..")]`.
+    patch: Option<Ident>,
+    /// Whether the table is created as a `STRICT` table.
+    strict: bool,
+    /// The fields that are stored in a column, in the order of the struct.
+    columns: Vec<TableField>,
+}
+
+impl DeriveTable {
+    pub fn parse(input: DeriveInput) -> Result<Self> {
+        let DeriveInput {
+            ident,
+            vis,
+            generics,
+            attrs,
+            data:
+                Data::Struct(DataStruct {
+                    fields: Fields::Named(fields),
+                    ..
+                }),
+            ..
+        } = input
+        else {
+            return Err(Error::new(
+                input.span(),
+                "expected struct with named fields",
+            ));
+        };
+
+        let container = ContainerAttrs::parse(attrs.clone())?;
+
+        if let Some(span) = container.borrow {
+            return Err(Error::new(
+                span,
+                "`Table` can't be derived for a struct that borrows from the row",
+            ));
+        }
+        let table = TableAttrs::parse(&attrs)?;
+
+        let Some(name) = table.name else {
+            return Err(Error::new(
+                ident.span(),
+                "expected the name of the table, like `#[table(name = \"..\")]`",
+            ));
+        };
+
+        if let Some(patch) = &table.patch {
+            if !generics.params.is_empty() {
+                return Err(Error::new(
+                    patch.span(),
+                    "`patch` isn't supported for generic structs",
+                ));
+            }
+        }
+
+        let mut columns = Vec::new();
+
+        for field in fields.named {
+            let table = TableFieldAttrs::parse(&field.attrs)?;
+            let span = field.span();
+            let vis = field.vis.clone();
+            let field = FromRowField::parse(field, &container, None)?;
+
+            match &field.attrs {
+                FromRowAttrs::Skip if table.is_set() => {
+                    return Err(Error::new(
+                        span,
+                        "can't combine `skip` with `table` attributes",
+                    ));
+                }
+                FromRowAttrs::Skip => continue,
+                FromRowAttrs::Field {
+                    index: None,
+                    convert,
+                    ..
+                } => {
+                    if let Some(attr) = convert.as_ref().and_then(irreversible) {
+                        return Err(Error::new(
+                            span,
+                            format!(
+                                "`Table` can't write a field that is read using `{attr}`, use `with` and a module providing `to_sql`, or `#[from_row(skip)]`"
+                            ),
+                        ));
+                    }
+                }
+                FromRowAttrs::Field { index: Some(_), .. } => {
+                    return Err(Error::new(
+                        span,
+                        "`Table` requires columns that are named, not mapped by `index`",
+                    ));
+                }
+                FromRowAttrs::Flatten { .. } | FromRowAttrs::Columns { .. } => {
+                    return Err(Error::new(
+                        span,
+                        "`Table` doesn't support `flatten` or `columns` fields",
+                    ));
+                }
+            }
+
+            columns.push(TableField { field, vis, table });
+        }
+
+        let primary_keys = columns
+            .iter()
+            .filter(|column| column.table.primary_key)
+            .count();
+
+        if primary_keys != 1 {
+            return Err(Error::new(
+                ident.span(),
+                format!(
+                    "a table should have exactly one `#[table(primary_key)]` field, found {primary_keys}"
+                ),
+            ));
+        }
+
+        Ok(Self {
+            ident,
+            vis,
+            generics,
+            name,
+            patch: table.patch,
+            strict: table.strict,
+            columns,
+        })
+    }
+
+    /// Generate the `Table` implementation.
+    pub fn generate(self) -> TokenStream2 {
+        let ident = &self.ident;
+        let name = &self.name;
+
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        let original_predicates = where_clause.map(|w| &w.predicates).into_iter();
+
+        let names = self.columns.iter().map(|column| column.field.column_name());
+        let types = self
+            .columns
+            .iter()
+            .filter(|column| column.module().is_none())
+            .map(|column| &column.field.ty);
+        let values = self.columns.iter().map(|column| {
+            let ident = &column.field.ident;
+
+            column.to_sql(quote!(&self.#ident))
+        });
+
+        let primary_key = self
+            .columns
+            .iter()
+            .find(|column| column.table.primary_key)
+            .expect("validated while parsing");
+        let primary_key_name = primary_key.field.column_name();
+        let primary_key = {
+            let ident = &primary_key.field.ident;
+
+            primary_key.to_sql(quote!(&self.#ident))
+        };
+
+        let create_table = self.create_table_sql();
+        let patch = self.generate_patch();
+
+        quote! {
+            #patch
+
+            impl #impl_generics rusqlite_from_row::table::Table for #ident #ty_generics
+            where
+                Self: rusqlite_from_row::FromRow,
+                #(#original_predicates,)*
+                #(#types: rusqlite_from_row::rusqlite::types::ToSql,)*
+            {
+                const NAME: &'static str = #name;
+                const COLUMNS: &'static [&'static str] = &[#(#names),*];
+                const PRIMARY_KEY: &'static str = #primary_key_name;
+
+                fn create_table_sql() -> std::string::String {
+                    std::string::String::from(#create_table)
+                }
+
+                fn primary_key(
+                    &self,
+                ) -> std::result::Result<
+                    rusqlite_from_row::rusqlite::types::ToSqlOutput<'_>,
+                    rusqlite_from_row::rusqlite::Error,
+                > {
+                    #primary_key
+                }
+
+                fn values(
+                    &self,
+                ) -> std::result::Result<
+                    std::vec::Vec<rusqlite_from_row::rusqlite::types::ToSqlOutput<'_>>,
+                    rusqlite_from_row::rusqlite::Error,
+                > {
+                    Ok(std::vec![#(#values?),*])
+                }
+            }
+        }
+    }
+
+    /// Returns the `CREATE TABLE` statement, with the column types derived from the fields.
+    fn create_table_sql(&self) -> String {
+        let columns = self
+            .columns
+            .iter()
+            .map(|column| column.definition(self.strict))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let strict = if self.strict { " STRICT" } else { "" };
+
+        format!(
+            "CREATE TABLE {} ({columns}){strict}",
+            quote_name(&self.name)
+        )
+    }
+
+    /// Generate the struct from `#[table(patch = "..")]`, which has an `Option` of every column
+    /// except the primary key, and updates the columns that are `Some`.
+    fn generate_patch(&self) -> Option<TokenStream2> {
+        let patch = self.patch.as_ref()?;
+        let ident = &self.ident;
+        let vis = &self.vis;
+
+        let primary_key = self
+            .columns
+            .iter()
+            .find(|column| column.table.primary_key)
+            .expect("validated while parsing");
+        let columns = self
+            .columns
+            .iter()
+            .filter(|column| !column.table.primary_key)
+            .collect::<Vec<_>>();
+
+        let fields = columns
+            .iter()
+            .map(|column| &column.field.ident)
+            .collect::<Vec<_>>();
+        let visibilities = columns.iter().map(|column| &column.vis);
+        let types = columns.iter().map(|column| &column.field.ty);
+        let parameters = fields
+            .iter()
+            .map(|field| format!(":{}", field.unraw()))
+            .collect::<Vec<_>>();
+        let values = columns.iter().map(|column| column.to_sql(quote!(value)));
+        let assignments = columns.iter().zip(&parameters).map(|(column, parameter)| {
+            format!("{} = {parameter}", quote_name(&column.field.column_name()))
+        });
+
+        let primary_key_parameter = format!(":{}", primary_key.field.ident.unraw());
+        let update = format!("UPDATE {} SET ", quote_name(&self.name));
+        let filter = format!(
+            " WHERE {} = {primary_key_parameter}",
+            quote_name(&primary_key.field.column_name()),
+        );
+
+        let doc = format!(
+            "Changes to a `{ident}`, where the columns that are `Some` are updated by the statement of `{patch}::sql`."
+        );
+        let field_docs = fields
+            .iter()
+            .map(|field| format!("The new value of `{field}`."));
+
+        Some(quote! {
+            #[doc = #doc]
+            #[derive(Default)]
+            #vis struct #patch {
+                #(
+                    #[doc = #field_docs]
+                    #visibilities #fields: std::option::Option<#types>,
+                )*
+            }
+
+            impl #patch {
+                /// Returns the `UPDATE` statement of the columns that are set, with a named
+                /// parameter for each of them and for the primary key, or `None` when no column
+                /// is set.
+                #vis fn sql(&self) -> std::option::Option<std::string::String> {
+                    let mut assignments = std::vec::Vec::<&str>::new();
+                    #(
+                        if self.#fields.is_some() {
+                            assignments.push(#assignments);
+                        }
+                    )*
+
+                    if assignments.is_empty() {
+                        return None;
+                    }
+
+                    Some(#update.to_string() + &assignments.join(", ") + #filter)
+                }
+
+                /// Returns the named parameters of the columns that are set, with their values
+                /// converted like [`Table::values`](rusqlite_from_row::table::Table::values).
+                #vis fn params(
+                    &self,
+                ) -> std::result::Result<
+                    std::vec::Vec<(&'static str, rusqlite_from_row::rusqlite::types::ToSqlOutput<'_>)>,
+                    rusqlite_from_row::rusqlite::Error,
+                > {
+                    let mut params = std::vec::Vec::new();
+                    #(
+                        if let Some(value) = &self.#fields {
+                            params.push((#parameters, #values?));
+                        }
+                    )*
+                    Ok(params)
+                }
+
+                /// Updates the columns that are set of the row with the primary key `id`,
+                /// returning the number of updated rows.
+                #vis fn execute(
+                    &self,
+                    connection: &rusqlite_from_row::rusqlite::Connection,
+                    id: impl rusqlite_from_row::rusqlite::types::ToSql,
+                ) -> std::result::Result<usize, rusqlite_from_row::rusqlite::Error> {
+                    let Some(sql) = self.sql() else {
+                        return Ok(0);
+                    };
+
+                    let params = self.params()?;
+                    let mut params = params
+                        .iter()
+                        .map(|(name, value)| (*name, value as &dyn rusqlite_from_row::rusqlite::types::ToSql))
+                        .collect::<std::vec::Vec<_>>();
+                    params.push((#primary_key_parameter, &id));
+
+                    connection.prepare_cached(&sql)?.execute(&*params)
+                }
+
+                /// Assigns the fields that are set to `value`.
+                #vis fn apply(self, value: &mut #ident) {
+                    #(
+                        if let Some(field) = self.#fields {
+                            value.#fields = field;
+                        }
+                    )*
+                }
+            }
+        })
+    }
+}
+
+/// A field that is stored in a column of the table.
+struct TableField {
+    field: FromRowField,
+    vis: Visibility,
+    table: TableFieldAttrs,
+}
+
+impl TableField {
+    /// Returns the module of a field using `with`, whose `to_sql` function writes the field.
+    fn module(&self) -> Option<&ExprPath> {
+        match &self.field.attrs {
+            FromRowAttrs::Field {
+                convert: Some(Convert::With(module)),
+                ..
+            } => Some(module),
+            _ => None,
+        }
+    }
+
+    /// Returns an expression that converts `value`, a reference to the field, into a
+    /// `Result<ToSqlOutput>`, using `ToSql` or the `to_sql` function of a `with` module.
+    ///
+    /// A lifted `Option` writes `None` as `NULL` and passes other values to `to_sql`.
+    fn to_sql(&self, value: TokenStream2) -> TokenStream2 {
+        let Some(module) = self.module() else {
+            return quote!(rusqlite_from_row::rusqlite::types::ToSql::to_sql(#value));
+        };
+
+        if self.field.lifted_ty().is_some() {
+            quote! {
+                match #value {
+                    Some(value) => #module::to_sql(value),
+                    None => Ok(rusqlite_from_row::rusqlite::types::ToSqlOutput::Owned(
+                        rusqlite_from_row::rusqlite::types::Value::Null,
+                    )),
+                }
+            }
+        } else {
+            quote!(#module::to_sql(#value))
+        }
+    }
+
+    /// Returns the definition of the column in a `CREATE TABLE` statement.
+    ///
+    /// Columns are `NOT NULL` unless the field is an `Option`, and have no type in a table that
+    /// isn't strict when it can't be derived from the field, which lets them store any value.
+    fn definition(&self, strict: bool) -> String {
+        let mut definition = quote_name(&self.field.column_name());
+
+        let nullable = option_inner(&self.field.ty);
+        let sql_type = match &self.table.sql_type {
+            Some(sql_type) => Some(sql_type.as_str()),
+            None => self.sql_type(nullable.unwrap_or(&self.field.ty)),
+        };
+
+        match sql_type {
+            Some(sql_type) => definition += &format!(" {sql_type}"),
+            None if strict => definition += " ANY",
+            None => {}
+        }
+
+        if self.table.primary_key {
+            definition += " PRIMARY KEY";
+        }
+
+        if nullable.is_none() {
+            definition += " NOT NULL";
+        }
+
+        if self.table.unique {
+            definition += " UNIQUE";
+        }
+
+        if let Some(default) = &self.table.default {
+            definition += &format!(" DEFAULT {default}");
+        }
+
+        if let Some(references) = &self.table.references {
+            definition += &format!(" REFERENCES {references}");
+        }
+
+        definition
+    }
+
+    /// Returns the type of the column for the type of the field without an `Option`, which is
+    /// unknown when the field is written by a `with` module.
+    fn sql_type(&self, ty: &Type) -> Option<&'static str> {
+        match self.module() {
+            Some(_) => None,
+            None => rust_sql_type(ty),
+        }
+    }
+}
+
+/// Returns the attribute of a conversion that can't be reversed to write the field, which
+/// excludes `with` and `coerce`.
+fn irreversible(convert: &Convert) -> Option<&'static str> {
+    match convert {
+        Convert::From(_) => Some("from"),
+        Convert::TryFrom(_) => Some("try_from"),
+        Convert::FromFn(_) => Some("from_fn"),
+        Convert::TryFromFn(_) => Some("try_from_fn"),
+        Convert::FromValue(_) => Some("from_value"),
+        Convert::Parse => Some("parse"),
+        Convert::Codec { .. } => Some("codec"),
+        Convert::Split { .. } => Some("split"),
+        Convert::JsonRows => Some("json_rows"),
+        Convert::DateTime { .. } => Some("datetime"),
+        Convert::With(_) | Convert::Coerce => None,
+    }
+}
+
+/// Maps a rust type to the type of a column, for the types that `rusqlite` stores as a single
+/// storage class.
+fn rust_sql_type(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::Reference(reference) => rust_sql_type(&reference.elem),
+        Type::Array(array) if is_u8(&array.elem) => Some("BLOB"),
+        Type::Slice(slice) if is_u8(&slice.elem) => Some("BLOB"),
+        Type::Path(path) => {
+            let segment = path.path.segments.last()?;
+
+            match segment.ident.to_string().as_str() {
+                "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize"
+                | "bool" => Some("INTEGER"),
+                "f32" | "f64" => Some("REAL"),
+                "String" | "str" => Some("TEXT"),
+                "Vec" | "Box" | "Rc" | "Arc" | "Cow" => {
+                    let ty = single_type_argument(segment)?;
+
+                    if segment.ident == "Vec" {
+                        is_u8(ty).then_some("BLOB")
+                    } else {
+                        rust_sql_type(ty)
+                    }
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn is_u8(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.path.is_ident("u8"))
+}
+
+/// Attributes specified on the struct itself using `#[table(..)]`.
+#[derive(Default)]
+struct TableAttrs {
+    name: Option<String>,
+    patch: Option<Ident>,
+    strict: bool,
+}
+
+impl TableAttrs {
+    fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut table = Self::default();
+
+        for attr in attrs {
+            if !attr.meta.path().is_ident("table") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    let name: LitStr = meta.value()?.parse()?;
+                    table.name = Some(name.value());
+                } else if meta.path.is_ident("patch") {
+                    let patch: LitStr = meta.value()?.parse()?;
+                    table.patch = Some(patch.parse()?);
+                } else if meta.path.is_ident("strict") {
+                    table.strict = true;
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(table)
+    }
+}
+
+/// Attributes specified on a field using `#[table(..)]`.
+#[derive(Default)]
+struct TableFieldAttrs {
+    primary_key: bool,
+    unique: bool,
+    /// The SQL expression of the default value of the column.
+    default: Option<String>,
+    /// The table and column referred to by a foreign key, like `user(id)`.
+    references: Option<String>,
+    /// The type of the column, which overrides the type derived from the field.
+    sql_type: Option<String>,
+}
+
+impl TableFieldAttrs {
+    fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut field = Self::default();
+
+        for attr in attrs {
+            if !attr.meta.path().is_ident("table") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("primary_key") {
+                    field.primary_key = true;
+                } else if meta.path.is_ident("unique") {
+                    field.unique = true;
+                } else if meta.path.is_ident("default") {
+                    let default: LitStr = meta.value()?.parse()?;
+                    field.default = Some(default.value());
+                } else if meta.path.is_ident("references") {
+                    let references: LitStr = meta.value()?.parse()?;
+                    field.references = Some(references.value());
+                } else if meta.path.is_ident("sql_type") {
+                    let sql_type: LitStr = meta.value()?.parse()?;
+                    field.sql_type = Some(sql_type.value());
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(field)
+    }
+
+    fn is_set(&self) -> bool {
+        self.primary_key
+            || self.unique
+            || self.default.is_some()
+            || self.references.is_some()
+            || self.sql_type.is_some()
+    }
+}
+
+/// Quotes a table or column name, like `rusqlite_from_row::table::quote`.
+fn quote_name(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
@@ -0,0 +1,167 @@
+//! Decoding of serialized `BLOB` columns, used by the `#[from_row(codec = "..")]` attribute.
+//!
+//! Each codec is enabled by the feature of the same name: `cbor`, `msgpack`, `bincode` and
+//! `postcard`. Compressed values are supported using the `zstd` and `deflate` features.
+
+use std::{borrow::Cow, error::Error, fmt};
+
+use rusqlite::RowIndex;
+pub use serde::de::DeserializeOwned;
+
+use crate::{conversion_error, from_sql_error};
+
+/// A serialization format used to decode a `BLOB` column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    /// CBOR, using `ciborium`.
+    #[cfg(feature = "cbor")]
+    Cbor,
+    /// MessagePack, using `rmp-serde`.
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+    /// The format of `bincode` 1.x, using its default options.
+    #[cfg(feature = "bincode")]
+    Bincode,
+    /// The format of `postcard`.
+    #[cfg(feature = "postcard")]
+    Postcard,
+}
+
+impl Codec {
+    /// The name of the codec, as used by the `codec` attribute.
+    pub fn name(self) -> &'static str {
+        match self {
+            #[cfg(feature = "cbor")]
+            Codec::Cbor => "cbor",
+            #[cfg(feature = "msgpack")]
+            Codec::MessagePack => "msgpack",
+            #[cfg(feature = "bincode")]
+            Codec::Bincode => "bincode",
+            #[cfg(feature = "postcard")]
+            Codec::Postcard => "postcard",
+        }
+    }
+
+    /// Deserializes `bytes` into `T`.
+    pub fn decode<T: DeserializeOwned>(
+        self,
+        bytes: &[u8],
+    ) -> Result<T, Box<dyn Error + Send + Sync>> {
+        match self {
+            #[cfg(feature = "cbor")]
+            Codec::Cbor => Ok(ciborium::from_reader(bytes)?),
+            #[cfg(feature = "msgpack")]
+            Codec::MessagePack => Ok(rmp_serde::from_slice(bytes)?),
+            #[cfg(feature = "bincode")]
+            Codec::Bincode => Ok(bincode::deserialize(bytes)?),
+            #[cfg(feature = "postcard")]
+            Codec::Postcard => Ok(postcard::from_bytes(bytes)?),
+        }
+    }
+}
+
+/// The compression applied to a `BLOB` column before it was stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// The value isn't compressed.
+    #[default]
+    None,
+    /// Zstandard, using `zstd`.
+    #[cfg(feature = "zstd")]
+    Zstd,
+    /// Raw deflate without a zlib or gzip header, using `flate2`.
+    #[cfg(feature = "deflate")]
+    Deflate,
+}
+
+impl Compression {
+    /// The name of the compression, as used by the `compress` attribute.
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => "zstd",
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => "deflate",
+        }
+    }
+
+    /// Decompresses `bytes`, which are returned as is when not compressed.
+    pub fn decompress(self, bytes: &[u8]) -> std::io::Result<Cow<'_, [u8]>> {
+        match self {
+            Compression::None => Ok(Cow::Borrowed(bytes)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => zstd::decode_all(bytes).map(Cow::Owned),
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => {
+                use std::io::Read;
+
+                let mut decompressed = Vec::new();
+                flate2::read::DeflateDecoder::new(bytes).read_to_end(&mut decompressed)?;
+
+                Ok(Cow::Owned(decompressed))
+            }
+        }
+    }
+}
+
+/// Decodes a `BLOB` column using `codec`, after decompressing it, like the
+/// `#[from_row(codec = "..", compress = "..")]` attribute does.
+///
+/// Will return an error if the column doesn't exist or isn't a `BLOB`, errors while decompressing
+/// or decoding are returned as a [`CodecError`] wrapped in a [`ConversionError`](crate::ConversionError)
+/// that names the column.
+pub fn get_decoded<T: DeserializeOwned, I: RowIndex>(
+    row: &rusqlite::Row,
+    idx: I,
+    codec: Codec,
+    compression: Compression,
+) -> Result<T, rusqlite::Error> {
+    let idx = idx.idx(row.as_ref())?;
+    let value = row.get_ref(idx)?;
+    let bytes = value
+        .as_blob()
+        .map_err(|err| from_sql_error(row, idx, value, err))?;
+
+    let bytes = compression.decompress(bytes).map_err(|err| {
+        conversion_error(row, idx, value, CodecError::new(compression.name(), err))
+    })?;
+
+    codec
+        .decode(&bytes)
+        .map_err(|err| conversion_error(row, idx, value, CodecError::new(codec.name(), err)))
+}
+
+/// Error returned when a `BLOB` column can't be decompressed or decoded.
+#[derive(Debug)]
+pub struct CodecError {
+    format: &'static str,
+    source: Box<dyn Error + Send + Sync>,
+}
+
+impl CodecError {
+    /// Creates a new error for the codec or compression named `format`.
+    pub fn new(format: &'static str, source: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        Self {
+            format,
+            source: source.into(),
+        }
+    }
+
+    /// The name of the codec or compression that failed.
+    pub fn format(&self) -> &'static str {
+        self.format
+    }
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "can't decode {}: {}", self.format, self.source)
+    }
+}
+
+impl Error for CodecError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.source)
+    }
+}
@@ -0,0 +1,250 @@
+//! Collecting query results into maps, keyed by a field marked as `#[from_row(key)]` or by a
+//! leading column.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt,
+    hash::{BuildHasher, Hash},
+};
+
+use rusqlite::{types::FromSql, Params, Statement};
+
+use crate::{FromRow, Keyed};
+
+/// What to do when more than one row has the same key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateKey {
+    /// Return a [`DuplicateKeyError`].
+    #[default]
+    Error,
+    /// Keep the first row with the key.
+    FirstWins,
+    /// Keep the last row with the key.
+    LastWins,
+}
+
+/// A map that rows can be collected into, implemented for [`HashMap`] and [`BTreeMap`].
+pub trait KeyedCollection<K, V>: Default {
+    /// Returns a mutable reference to the value of `key`, if any.
+    fn get_mut(&mut self, key: &K) -> Option<&mut V>;
+
+    /// Inserts a value for a key that isn't present.
+    fn insert(&mut self, key: K, value: V);
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Default> KeyedCollection<K, V> for HashMap<K, V, S> {
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        HashMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        HashMap::insert(self, key, value);
+    }
+}
+
+impl<K: Ord, V> KeyedCollection<K, V> for BTreeMap<K, V> {
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        BTreeMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        BTreeMap::insert(self, key, value);
+    }
+}
+
+/// Extends [`Statement`] with queries that collect rows mapped using [`FromRow`] into maps.
+///
+/// Rows are keyed either by their [`Keyed::key`], which is implemented by marking a field as
+/// `#[from_row(key)]`, or by a leading column using the `_by_column` variants. In that case the
+/// key is read from the first column, and positional fields of the row are read starting from
+/// the second column.
+pub trait StatementExt {
+    /// Executes the query and collects the rows into a map by their key, handling rows with the
+    /// same key according to `duplicates`.
+    fn query_map_keyed<T, M, P>(
+        &mut self,
+        params: P,
+        duplicates: DuplicateKey,
+    ) -> Result<M, rusqlite::Error>
+    where
+        T: FromRow + Keyed,
+        T::Key: Clone + fmt::Debug,
+        M: KeyedCollection<T::Key, T>,
+        P: Params;
+
+    /// Executes the query and collects the rows into a map by the value of the first column,
+    /// handling rows with the same key according to `duplicates`.
+    fn query_map_keyed_by_column<K, T, M, P>(
+        &mut self,
+        params: P,
+        duplicates: DuplicateKey,
+    ) -> Result<M, rusqlite::Error>
+    where
+        K: FromSql + fmt::Debug,
+        T: FromRow,
+        M: KeyedCollection<K, T>,
+        P: Params;
+
+    /// Executes the query and groups the rows by their key, in the order returned by the query.
+    fn query_grouped<T, M, P>(&mut self, params: P) -> Result<M, rusqlite::Error>
+    where
+        T: FromRow + Keyed,
+        T::Key: Clone,
+        M: KeyedCollection<T::Key, Vec<T>>,
+        P: Params;
+
+    /// Executes the query and groups the rows by the value of the first column, in the order
+    /// returned by the query.
+    fn query_grouped_by_column<K, T, M, P>(&mut self, params: P) -> Result<M, rusqlite::Error>
+    where
+        K: FromSql,
+        T: FromRow,
+        M: KeyedCollection<K, Vec<T>>,
+        P: Params;
+}
+
+impl StatementExt for Statement<'_> {
+    fn query_map_keyed<T, M, P>(
+        &mut self,
+        params: P,
+        duplicates: DuplicateKey,
+    ) -> Result<M, rusqlite::Error>
+    where
+        T: FromRow + Keyed,
+        T::Key: Clone + fmt::Debug,
+        M: KeyedCollection<T::Key, T>,
+        P: Params,
+    {
+        let mut map = M::default();
+
+        for value in self.query_map(params, T::try_from_row)? {
+            let value = value?;
+
+            insert(&mut map, value.key().clone(), value, duplicates)?;
+        }
+
+        Ok(map)
+    }
+
+    fn query_map_keyed_by_column<K, T, M, P>(
+        &mut self,
+        params: P,
+        duplicates: DuplicateKey,
+    ) -> Result<M, rusqlite::Error>
+    where
+        K: FromSql + fmt::Debug,
+        T: FromRow,
+        M: KeyedCollection<K, T>,
+        P: Params,
+    {
+        let mut map = M::default();
+
+        for entry in self.query_map(params, keyed_by_column)? {
+            let (key, value) = entry?;
+
+            insert(&mut map, key, value, duplicates)?;
+        }
+
+        Ok(map)
+    }
+
+    fn query_grouped<T, M, P>(&mut self, params: P) -> Result<M, rusqlite::Error>
+    where
+        T: FromRow + Keyed,
+        T::Key: Clone,
+        M: KeyedCollection<T::Key, Vec<T>>,
+        P: Params,
+    {
+        let mut map = M::default();
+
+        for value in self.query_map(params, T::try_from_row)? {
+            let value = value?;
+
+            push(&mut map, value.key().clone(), value);
+        }
+
+        Ok(map)
+    }
+
+    fn query_grouped_by_column<K, T, M, P>(&mut self, params: P) -> Result<M, rusqlite::Error>
+    where
+        K: FromSql,
+        T: FromRow,
+        M: KeyedCollection<K, Vec<T>>,
+        P: Params,
+    {
+        let mut map = M::default();
+
+        for entry in self.query_map(params, keyed_by_column)? {
+            let (key, value) = entry?;
+
+            push(&mut map, key, value);
+        }
+
+        Ok(map)
+    }
+}
+
+/// Reads the key from the first column and the value from the rest of the row.
+fn keyed_by_column<K: FromSql, T: FromRow>(row: &rusqlite::Row) -> Result<(K, T), rusqlite::Error> {
+    Ok((row.get(0)?, T::try_from_row_offset(row, None, 1)?))
+}
+
+fn insert<K: fmt::Debug, V, M: KeyedCollection<K, V>>(
+    map: &mut M,
+    key: K,
+    value: V,
+    duplicates: DuplicateKey,
+) -> Result<(), DuplicateKeyError> {
+    match (map.get_mut(&key), duplicates) {
+        (None, _) => map.insert(key, value),
+        (Some(_), DuplicateKey::Error) => return Err(DuplicateKeyError::new(format!("{key:?}"))),
+        (Some(_), DuplicateKey::FirstWins) => {}
+        (Some(existing), DuplicateKey::LastWins) => *existing = value,
+    }
+
+    Ok(())
+}
+
+fn push<K, V, M: KeyedCollection<K, Vec<V>>>(map: &mut M, key: K, value: V) {
+    match map.get_mut(&key) {
+        Some(values) => values.push(value),
+        None => map.insert(key, vec![value]),
+    }
+}
+
+/// Error returned when more than one row has the same key, when using [`DuplicateKey::Error`].
+///
+/// This is converted into a [`rusqlite::Error::ToSqlConversionFailure`], as it concerns rows
+/// instead of a column.
+#[derive(Debug)]
+pub struct DuplicateKeyError {
+    key: String,
+}
+
+impl DuplicateKeyError {
+    /// Creates a new error for the key formatted as `key`.
+    pub fn new(key: impl Into<String>) -> Self {
+        Self { key: key.into() }
+    }
+
+    /// The duplicate key, formatted using `Debug`.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl fmt::Display for DuplicateKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "more than one row has key `{}`", self.key)
+    }
+}
+
+impl Error for DuplicateKeyError {}
+
+impl From<DuplicateKeyError> for rusqlite::Error {
+    fn from(err: DuplicateKeyError) -> Self {
+        crate::row_error(err)
+    }
+}
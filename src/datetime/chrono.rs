@@ -0,0 +1,85 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use rusqlite::types::{FromSqlResult, ValueRef};
+
+use super::{decode, invalid, Civil, DateTimeOptions, Decoded, FromSqlDateTime, Zone};
+
+fn naive(civil: &Civil) -> Option<NaiveDateTime> {
+    NaiveDate::from_ymd_opt(civil.year, civil.month.into(), civil.day.into())?.and_hms_nano_opt(
+        civil.hour.into(),
+        civil.minute.into(),
+        civil.second.into(),
+        civil.nanosecond,
+    )
+}
+
+fn in_zone(datetime: DateTime<Utc>, zone: Zone) -> DateTime<FixedOffset> {
+    match zone {
+        Zone::Utc => datetime.fixed_offset(),
+        Zone::Local => datetime.with_timezone(&Local).fixed_offset(),
+    }
+}
+
+fn decode_fixed(decoded: Decoded, options: &DateTimeOptions) -> Option<DateTime<FixedOffset>> {
+    match decoded {
+        Decoded::Timestamp(nanos) => {
+            let seconds = i64::try_from(nanos.div_euclid(1_000_000_000)).ok()?;
+            let nanos = nanos.rem_euclid(1_000_000_000) as u32;
+
+            Some(in_zone(
+                DateTime::from_timestamp(seconds, nanos)?,
+                options.zone(),
+            ))
+        }
+        Decoded::Civil(civil) => {
+            let naive = naive(&civil)?;
+
+            match (civil.offset, options.zone()) {
+                (Some(offset), _) => FixedOffset::east_opt(offset)?
+                    .from_local_datetime(&naive)
+                    .single(),
+                (None, Zone::Utc) => Some(naive.and_utc().fixed_offset()),
+                (None, Zone::Local) => Local
+                    .from_local_datetime(&naive)
+                    .earliest()
+                    .map(|datetime| datetime.fixed_offset()),
+            }
+        }
+    }
+}
+
+fn from_sql_fixed(
+    value: ValueRef<'_>,
+    options: &DateTimeOptions,
+) -> FromSqlResult<DateTime<FixedOffset>> {
+    decode_fixed(decode(value, options)?, options).ok_or_else(|| invalid(value))
+}
+
+impl FromSqlDateTime for DateTime<FixedOffset> {
+    fn from_sql_datetime(value: ValueRef<'_>, options: &DateTimeOptions) -> FromSqlResult<Self> {
+        from_sql_fixed(value, options)
+    }
+}
+
+impl FromSqlDateTime for DateTime<Utc> {
+    fn from_sql_datetime(value: ValueRef<'_>, options: &DateTimeOptions) -> FromSqlResult<Self> {
+        from_sql_fixed(value, options).map(|datetime| datetime.with_timezone(&Utc))
+    }
+}
+
+impl FromSqlDateTime for DateTime<Local> {
+    fn from_sql_datetime(value: ValueRef<'_>, options: &DateTimeOptions) -> FromSqlResult<Self> {
+        from_sql_fixed(value, options).map(|datetime| datetime.with_timezone(&Local))
+    }
+}
+
+impl FromSqlDateTime for NaiveDateTime {
+    fn from_sql_datetime(value: ValueRef<'_>, options: &DateTimeOptions) -> FromSqlResult<Self> {
+        match decode(value, options)? {
+            Decoded::Civil(civil) if civil.offset.is_none() => naive(&civil),
+            decoded => decode_fixed(decoded, options).map(|datetime| {
+                in_zone(datetime.with_timezone(&Utc), options.zone()).naive_local()
+            }),
+        }
+        .ok_or_else(|| invalid(value))
+    }
+}
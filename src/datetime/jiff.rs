@@ -0,0 +1,67 @@
+use jiff::{
+    civil,
+    tz::{Offset, TimeZone},
+    Timestamp, Zoned,
+};
+use rusqlite::types::{FromSqlResult, ValueRef};
+
+use super::{decode, invalid, Civil, DateTimeOptions, Decoded, FromSqlDateTime, Zone};
+
+fn datetime(civil: &Civil) -> Option<civil::DateTime> {
+    civil::DateTime::new(
+        i16::try_from(civil.year).ok()?,
+        civil.month as i8,
+        civil.day as i8,
+        civil.hour as i8,
+        civil.minute as i8,
+        civil.second as i8,
+        civil.nanosecond as i32,
+    )
+    .ok()
+}
+
+fn time_zone(zone: Zone) -> TimeZone {
+    match zone {
+        Zone::Utc => TimeZone::UTC,
+        Zone::Local => TimeZone::system(),
+    }
+}
+
+fn decode_zoned(decoded: Decoded, options: &DateTimeOptions) -> Option<Zoned> {
+    match decoded {
+        Decoded::Timestamp(nanos) => Timestamp::from_nanosecond(nanos)
+            .ok()
+            .map(|timestamp| timestamp.to_zoned(time_zone(options.zone()))),
+        Decoded::Civil(civil) => {
+            let time_zone = match civil.offset {
+                Some(offset) => TimeZone::fixed(Offset::from_seconds(offset).ok()?),
+                None => time_zone(options.zone()),
+            };
+
+            datetime(&civil)?.to_zoned(time_zone).ok()
+        }
+    }
+}
+
+impl FromSqlDateTime for Zoned {
+    fn from_sql_datetime(value: ValueRef<'_>, options: &DateTimeOptions) -> FromSqlResult<Self> {
+        decode_zoned(decode(value, options)?, options).ok_or_else(|| invalid(value))
+    }
+}
+
+impl FromSqlDateTime for Timestamp {
+    fn from_sql_datetime(value: ValueRef<'_>, options: &DateTimeOptions) -> FromSqlResult<Self> {
+        Zoned::from_sql_datetime(value, options).map(|zoned| zoned.timestamp())
+    }
+}
+
+impl FromSqlDateTime for civil::DateTime {
+    fn from_sql_datetime(value: ValueRef<'_>, options: &DateTimeOptions) -> FromSqlResult<Self> {
+        match decode(value, options)? {
+            Decoded::Civil(civil) if civil.offset.is_none() => datetime(&civil),
+            decoded => decode_zoned(decoded, options)
+                .map(|zoned| time_zone(options.zone()).to_datetime(zoned.timestamp())),
+        }
+        .ok_or_else(|| invalid(value))
+    }
+}
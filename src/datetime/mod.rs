@@ -0,0 +1,285 @@
+//! Decoding of datetimes stored in any of the formats SQLite supports, used by the
+//! `#[from_row(datetime)]` attribute.
+//!
+//! SQLite has no datetime type, instead datetimes are stored as either:
+//!
+//! - `TEXT` in ISO-8601 format, like `2024-01-01 12:00:00.000` or `2024-01-01T12:00:00+02:00`.
+//! - `REAL` as a Julian day number.
+//! - `INTEGER` as time since the unix epoch, in seconds by default.
+//!
+//! All three formats are accepted by [`FromSqlDateTime`], which is implemented for the datetime
+//! types of `chrono`, `time` and `jiff` when enabling the feature of the same name.
+
+#[cfg(feature = "chrono")]
+mod chrono;
+#[cfg(feature = "jiff")]
+mod jiff;
+#[cfg(feature = "time")]
+mod time;
+
+use std::{error::Error, fmt};
+
+use rusqlite::types::{FromSqlError, FromSqlResult, ValueRef};
+
+/// A type that can be decoded from a datetime stored as `TEXT`, `REAL` or `INTEGER`.
+pub trait FromSqlDateTime: Sized {
+    /// Decodes a SQLite value into this type.
+    fn from_sql_datetime(value: ValueRef<'_>, options: &DateTimeOptions) -> FromSqlResult<Self>;
+}
+
+impl<T: FromSqlDateTime> FromSqlDateTime for Option<T> {
+    fn from_sql_datetime(value: ValueRef<'_>, options: &DateTimeOptions) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Null => Ok(None),
+            value => T::from_sql_datetime(value, options).map(Some),
+        }
+    }
+}
+
+/// Options that control how a datetime is decoded, specified using
+/// `#[from_row(datetime(unit = "..", timezone = ".."))]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DateTimeOptions {
+    unit: EpochUnit,
+    zone: Zone,
+}
+
+impl DateTimeOptions {
+    /// Creates new options.
+    pub const fn new(unit: EpochUnit, zone: Zone) -> Self {
+        Self { unit, zone }
+    }
+
+    /// The unit of datetimes stored as an `INTEGER`.
+    pub fn unit(&self) -> EpochUnit {
+        self.unit
+    }
+
+    /// The time zone of datetimes stored without an offset, and of decoded types without one.
+    pub fn zone(&self) -> Zone {
+        self.zone
+    }
+}
+
+/// The unit of datetimes stored as an `INTEGER` since the unix epoch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EpochUnit {
+    /// Seconds, the unit used by SQLite's `unixepoch()` function.
+    #[default]
+    Seconds,
+    /// Milliseconds.
+    Millis,
+    /// Microseconds.
+    Micros,
+    /// Nanoseconds.
+    Nanos,
+}
+
+impl EpochUnit {
+    fn nanos(self) -> i128 {
+        match self {
+            EpochUnit::Seconds => 1_000_000_000,
+            EpochUnit::Millis => 1_000_000,
+            EpochUnit::Micros => 1_000,
+            EpochUnit::Nanos => 1,
+        }
+    }
+}
+
+/// The time zone used for datetimes that are stored without an offset, like
+/// `2024-01-01 12:00:00`.
+///
+/// When decoding into a type without a time zone, like `chrono::NaiveDateTime`, values stored
+/// with an offset or as a timestamp are converted into this time zone.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Zone {
+    /// UTC, which is what SQLite's date and time functions use.
+    #[default]
+    Utc,
+    /// The local time zone of the system.
+    Local,
+}
+
+/// A datetime decoded from one of the storage formats.
+pub(crate) enum Decoded {
+    /// Nanoseconds since the unix epoch, from `REAL` or `INTEGER` values.
+    Timestamp(i128),
+    /// A calendar date and wall clock time, from `TEXT` values.
+    Civil(Civil),
+}
+
+/// A calendar date and wall clock time parsed from ISO-8601 text.
+pub(crate) struct Civil {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub nanosecond: u32,
+    /// The offset from UTC in seconds, if the text specified one.
+    pub offset: Option<i32>,
+}
+
+/// The Julian day of the unix epoch.
+const UNIX_EPOCH_JULIAN_DAY: f64 = 2_440_587.5;
+
+/// Decodes a SQLite value into either a timestamp or a civil datetime.
+pub(crate) fn decode(value: ValueRef<'_>, options: &DateTimeOptions) -> FromSqlResult<Decoded> {
+    match value {
+        ValueRef::Integer(value) => {
+            Ok(Decoded::Timestamp(i128::from(value) * options.unit.nanos()))
+        }
+        ValueRef::Real(julian_day) if julian_day.is_finite() => {
+            // Julian days only have millisecond precision in SQLite.
+            let millis = ((julian_day - UNIX_EPOCH_JULIAN_DAY) * 86_400_000.0).round();
+
+            Ok(Decoded::Timestamp(millis as i128 * 1_000_000))
+        }
+        ValueRef::Text(_) => {
+            let text = value.as_str()?;
+
+            parse_iso8601(text)
+                .map(Decoded::Civil)
+                .ok_or_else(|| invalid(value))
+        }
+        _ => Err(FromSqlError::InvalidType),
+    }
+}
+
+/// Returns an error for a datetime that can't be parsed or represented.
+pub(crate) fn invalid(value: ValueRef<'_>) -> FromSqlError {
+    let value = match value {
+        ValueRef::Integer(value) => value.to_string(),
+        ValueRef::Real(value) => value.to_string(),
+        value => String::from_utf8_lossy(value.as_bytes().unwrap_or_default()).into_owned(),
+    };
+
+    FromSqlError::Other(Box::new(InvalidDateTime(value)))
+}
+
+/// Error returned for a datetime that can't be parsed or represented by the decoded type.
+#[derive(Debug)]
+struct InvalidDateTime(String);
+
+impl fmt::Display for InvalidDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid datetime `{}`", self.0)
+    }
+}
+
+impl Error for InvalidDateTime {}
+
+/// Parses the ISO-8601 formats accepted by SQLite's date and time functions:
+/// `YYYY-MM-DD`, optionally followed by `T` or a space and `HH:MM`, `HH:MM:SS` or
+/// `HH:MM:SS.SSS`, optionally followed by `Z` or an offset like `+HH:MM`.
+fn parse_iso8601(text: &str) -> Option<Civil> {
+    let mut parser = Parser(text.trim().as_bytes());
+
+    let year = parser.number(4)? as i32;
+    parser.expect(b'-')?;
+    let month = parser.number(2)? as u8;
+    parser.expect(b'-')?;
+    let day = parser.number(2)? as u8;
+
+    let mut civil = Civil {
+        year,
+        month,
+        day,
+        hour: 0,
+        minute: 0,
+        second: 0,
+        nanosecond: 0,
+        offset: None,
+    };
+
+    if parser.is_empty() {
+        return Some(civil);
+    }
+
+    parser.expect(b'T').or_else(|| parser.expect(b' '))?;
+    civil.hour = parser.number(2)? as u8;
+    parser.expect(b':')?;
+    civil.minute = parser.number(2)? as u8;
+
+    if parser.expect(b':').is_some() {
+        civil.second = parser.number(2)? as u8;
+
+        if parser.expect(b'.').is_some() {
+            let digits = parser.digits();
+
+            if digits.is_empty() {
+                return None;
+            }
+
+            // Digits beyond nanosecond precision are truncated.
+            let fraction = &digits[..digits.len().min(9)];
+            let nanos = fraction
+                .iter()
+                .fold(0, |nanos, digit| nanos * 10 + u32::from(digit - b'0'));
+            civil.nanosecond = nanos * 10u32.pow(9 - fraction.len() as u32);
+        }
+    }
+
+    let _ = parser.expect(b' ');
+
+    if parser
+        .expect(b'Z')
+        .or_else(|| parser.expect(b'z'))
+        .is_some()
+    {
+        civil.offset = Some(0);
+    } else if let Some(sign) = parser.expect(b'+').or_else(|| parser.expect(b'-')) {
+        let hours = parser.number(2)? as i32;
+        let _ = parser.expect(b':');
+        let minutes = parser.number(2)? as i32;
+        let offset = hours * 3600 + minutes * 60;
+
+        civil.offset = Some(if sign == b'-' { -offset } else { offset });
+    }
+
+    parser.is_empty().then_some(civil)
+}
+
+struct Parser<'a>(&'a [u8]);
+
+impl<'a> Parser<'a> {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<u8> {
+        let (&first, rest) = self.0.split_first()?;
+
+        if first != byte {
+            return None;
+        }
+
+        self.0 = rest;
+        Some(first)
+    }
+
+    fn digits(&mut self) -> &'a [u8] {
+        let len = self.0.iter().take_while(|b| b.is_ascii_digit()).count();
+        let (digits, rest) = self.0.split_at(len);
+
+        self.0 = rest;
+        digits
+    }
+
+    /// Parses a number of exactly `len` digits.
+    fn number(&mut self, len: usize) -> Option<u32> {
+        let digits = self.0.get(..len)?;
+
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+
+        self.0 = &self.0[len..];
+        Some(
+            digits
+                .iter()
+                .fold(0, |number, digit| number * 10 + u32::from(digit - b'0')),
+        )
+    }
+}
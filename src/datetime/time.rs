@@ -0,0 +1,62 @@
+use rusqlite::types::{FromSqlResult, ValueRef};
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+use super::{decode, invalid, Civil, DateTimeOptions, Decoded, FromSqlDateTime, Zone};
+
+fn primitive(civil: &Civil) -> Option<PrimitiveDateTime> {
+    let date =
+        Date::from_calendar_date(civil.year, Month::try_from(civil.month).ok()?, civil.day).ok()?;
+    let time =
+        Time::from_hms_nano(civil.hour, civil.minute, civil.second, civil.nanosecond).ok()?;
+
+    Some(PrimitiveDateTime::new(date, time))
+}
+
+/// Returns the offset of `zone` at the given instant.
+///
+/// The local offset can't always be determined, see [`UtcOffset::local_offset_at`].
+fn offset_at(datetime: OffsetDateTime, zone: Zone) -> Option<UtcOffset> {
+    match zone {
+        Zone::Utc => Some(UtcOffset::UTC),
+        Zone::Local => UtcOffset::local_offset_at(datetime).ok(),
+    }
+}
+
+fn decode_offset(decoded: Decoded, options: &DateTimeOptions) -> Option<OffsetDateTime> {
+    match decoded {
+        Decoded::Timestamp(nanos) => {
+            let datetime = OffsetDateTime::from_unix_timestamp_nanos(nanos).ok()?;
+
+            Some(datetime.to_offset(offset_at(datetime, options.zone())?))
+        }
+        Decoded::Civil(civil) => {
+            let primitive = primitive(&civil)?;
+            let offset = match civil.offset {
+                Some(offset) => UtcOffset::from_whole_seconds(offset).ok()?,
+                None => offset_at(primitive.assume_utc(), options.zone())?,
+            };
+
+            Some(primitive.assume_offset(offset))
+        }
+    }
+}
+
+impl FromSqlDateTime for OffsetDateTime {
+    fn from_sql_datetime(value: ValueRef<'_>, options: &DateTimeOptions) -> FromSqlResult<Self> {
+        decode_offset(decode(value, options)?, options).ok_or_else(|| invalid(value))
+    }
+}
+
+impl FromSqlDateTime for PrimitiveDateTime {
+    fn from_sql_datetime(value: ValueRef<'_>, options: &DateTimeOptions) -> FromSqlResult<Self> {
+        match decode(value, options)? {
+            Decoded::Civil(civil) if civil.offset.is_none() => primitive(&civil),
+            decoded => decode_offset(decoded, options).and_then(|datetime| {
+                let datetime = datetime.to_offset(offset_at(datetime, options.zone())?);
+
+                Some(PrimitiveDateTime::new(datetime.date(), datetime.time()))
+            }),
+        }
+        .ok_or_else(|| invalid(value))
+    }
+}
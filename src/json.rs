@@ -0,0 +1,38 @@
+//! Decoding of JSON `TEXT` columns, used by the `#[from_row(json_rows)]` attribute.
+//!
+//! This is enabled by the `json` feature, which parses the text using `serde_json`.
+
+use rusqlite::{types::ValueRef, RowIndex};
+pub use serde::de::DeserializeOwned;
+
+use crate::{conversion_error, from_sql_error};
+
+/// Decodes a column holding a JSON array, like the output of
+/// `json_group_array(json_object(..))`, by deserializing each element, like the
+/// `#[from_row(json_rows)]` attribute does.
+///
+/// An SQL `null` produces an empty collection.
+///
+/// Will return an error if the column doesn't exist or isn't text, errors while deserializing
+/// are wrapped in a [`ConversionError`](crate::ConversionError) that names the column.
+pub fn get_json_rows<C, T, I>(row: &rusqlite::Row, idx: I) -> Result<C, rusqlite::Error>
+where
+    C: FromIterator<T>,
+    T: DeserializeOwned,
+    I: RowIndex,
+{
+    let idx = idx.idx(row.as_ref())?;
+    let value = row.get_ref(idx)?;
+
+    let json = match value {
+        ValueRef::Null => return Ok(C::from_iter(std::iter::empty())),
+        value => value
+            .as_str()
+            .map_err(|err| from_sql_error(row, idx, value, err))?,
+    };
+
+    let rows: Vec<T> =
+        serde_json::from_str(json).map_err(|err| conversion_error(row, idx, value, err))?;
+
+    Ok(rows.into_iter().collect())
+}
@@ -2,7 +2,32 @@
 #![doc = include_str!("../README.md")]
 
 pub use rusqlite;
-pub use rusqlite_from_row_derive::FromRow;
+pub use rusqlite_from_row_derive::{FromRow, Table};
+pub use table::Table;
+
+#[cfg(any(
+    feature = "cbor",
+    feature = "msgpack",
+    feature = "bincode",
+    feature = "postcard"
+))]
+pub mod codec;
+pub mod collect;
+#[cfg(any(feature = "chrono", feature = "time", feature = "jiff"))]
+pub mod datetime;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod relation;
+pub mod returning;
+pub mod table;
+pub mod tree;
+
+use std::{any::type_name, error::Error, fmt, rc::Rc, str::FromStr, sync::Arc};
+
+use rusqlite::{
+    types::{FromSql, FromSqlError, FromSqlResult, Value, ValueRef},
+    RowIndex,
+};
 
 /// A trait that allows mapping a [`rusqlite::Row`] to other types.
 pub trait FromRow: Sized {
@@ -41,10 +66,74 @@ pub trait FromRow: Sized {
         prefix: Option<&str>,
     ) -> Result<Self, rusqlite::Error>;
 
+    /// Try's to perform the conversion. Each row will be extracted using it's name prefixed with
+    /// `prefix`, columns that are mapped by position are read relative to `offset`.
+    ///
+    /// The default implementation ignores `offset`.
+    ///
+    /// Will return an error if the row does not contain the expected columns.
+    #[doc(hidden)]
+    fn try_from_row_offset(
+        row: &rusqlite::Row,
+        prefix: Option<&str>,
+        offset: usize,
+    ) -> Result<Self, rusqlite::Error> {
+        let _ = offset;
+        Self::try_from_row_prefixed(row, prefix)
+    }
+
     /// Try's to check if all the columns that are needed by this struct are sql 'null' values.
     ///
     /// Will return an error if the row does not contain the expected column names.
     fn is_all_null(row: &rusqlite::Row, prefix: Option<&str>) -> Result<bool, rusqlite::Error>;
+
+    /// Try's to check if all the columns that are needed by this struct are sql 'null' values,
+    /// columns that are mapped by position are read relative to `offset`.
+    ///
+    /// The default implementation ignores `offset`.
+    ///
+    /// Will return an error if the row does not contain the expected columns.
+    #[doc(hidden)]
+    fn is_all_null_offset(
+        row: &rusqlite::Row,
+        prefix: Option<&str>,
+        offset: usize,
+    ) -> Result<bool, rusqlite::Error> {
+        let _ = offset;
+        Self::is_all_null(row, prefix)
+    }
+
+    /// Try's to perform the conversion like [`FromRow::try_from_row_offset`], where `depth` is the
+    /// number of fields with `#[from_row(max_depth = ..)]` that were flattened to reach this
+    /// struct, which limits the recursion of recursive structs.
+    ///
+    /// The default implementation ignores `depth`.
+    #[doc(hidden)]
+    fn try_from_row_depth(
+        row: &rusqlite::Row,
+        prefix: Option<&str>,
+        offset: usize,
+        depth: usize,
+    ) -> Result<Self, rusqlite::Error> {
+        let _ = depth;
+        Self::try_from_row_offset(row, prefix, offset)
+    }
+
+    /// Try's to check if all the columns that are needed by this struct are sql 'null' values
+    /// like [`FromRow::is_all_null_offset`], where `depth` is the number of fields with
+    /// `#[from_row(max_depth = ..)]` that were flattened to reach this struct.
+    ///
+    /// The default implementation ignores `depth`.
+    #[doc(hidden)]
+    fn is_all_null_depth(
+        row: &rusqlite::Row,
+        prefix: Option<&str>,
+        offset: usize,
+        depth: usize,
+    ) -> Result<bool, rusqlite::Error> {
+        let _ = depth;
+        Self::is_all_null_offset(row, prefix, offset)
+    }
 }
 
 impl<T: FromRow> FromRow for Option<T> {
@@ -52,14 +141,610 @@ impl<T: FromRow> FromRow for Option<T> {
         row: &rusqlite::Row,
         prefix: Option<&str>,
     ) -> Result<Self, rusqlite::Error> {
-        if T::is_all_null(row, prefix)? {
+        Self::try_from_row_offset(row, prefix, 0)
+    }
+
+    fn try_from_row_offset(
+        row: &rusqlite::Row,
+        prefix: Option<&str>,
+        offset: usize,
+    ) -> Result<Self, rusqlite::Error> {
+        Self::try_from_row_depth(row, prefix, offset, 0)
+    }
+
+    fn is_all_null(row: &rusqlite::Row, prefix: Option<&str>) -> Result<bool, rusqlite::Error> {
+        T::is_all_null_depth(row, prefix, 0, 0)
+    }
+
+    fn is_all_null_offset(
+        row: &rusqlite::Row,
+        prefix: Option<&str>,
+        offset: usize,
+    ) -> Result<bool, rusqlite::Error> {
+        T::is_all_null_depth(row, prefix, offset, 0)
+    }
+
+    fn try_from_row_depth(
+        row: &rusqlite::Row,
+        prefix: Option<&str>,
+        offset: usize,
+        depth: usize,
+    ) -> Result<Self, rusqlite::Error> {
+        if T::is_all_null_depth(row, prefix, offset, depth)? {
             Ok(None)
         } else {
-            Ok(Some(T::try_from_row_prefixed(row, prefix)?))
+            Ok(Some(T::try_from_row_depth(row, prefix, offset, depth)?))
         }
     }
 
-    fn is_all_null(row: &rusqlite::Row, prefix: Option<&str>) -> Result<bool, rusqlite::Error> {
-        T::is_all_null(row, prefix)
+    fn is_all_null_depth(
+        row: &rusqlite::Row,
+        prefix: Option<&str>,
+        offset: usize,
+        depth: usize,
+    ) -> Result<bool, rusqlite::Error> {
+        T::is_all_null_depth(row, prefix, offset, depth)
+    }
+}
+
+/// A type that is identified by a key, like a primary key.
+///
+/// This is implemented by the derive macro for structs with a field marked as
+/// `#[from_row(key)]`, and used to collect rows into maps and trees.
+pub trait Keyed {
+    /// The type of the key.
+    type Key;
+
+    /// The key of this value.
+    fn key(&self) -> &Self::Key;
+}
+
+macro_rules! impl_from_row_for_pointer {
+    ($($pointer:ident),*) => {
+        $(
+            impl<T: FromRow> FromRow for $pointer<T> {
+                fn try_from_row_prefixed(
+                    row: &rusqlite::Row,
+                    prefix: Option<&str>,
+                ) -> Result<Self, rusqlite::Error> {
+                    T::try_from_row_offset(row, prefix, 0).map($pointer::new)
+                }
+
+                fn try_from_row_offset(
+                    row: &rusqlite::Row,
+                    prefix: Option<&str>,
+                    offset: usize,
+                ) -> Result<Self, rusqlite::Error> {
+                    T::try_from_row_offset(row, prefix, offset).map($pointer::new)
+                }
+
+                fn is_all_null(
+                    row: &rusqlite::Row,
+                    prefix: Option<&str>,
+                ) -> Result<bool, rusqlite::Error> {
+                    T::is_all_null_offset(row, prefix, 0)
+                }
+
+                fn is_all_null_offset(
+                    row: &rusqlite::Row,
+                    prefix: Option<&str>,
+                    offset: usize,
+                ) -> Result<bool, rusqlite::Error> {
+                    T::is_all_null_offset(row, prefix, offset)
+                }
+
+                fn try_from_row_depth(
+                    row: &rusqlite::Row,
+                    prefix: Option<&str>,
+                    offset: usize,
+                    depth: usize,
+                ) -> Result<Self, rusqlite::Error> {
+                    T::try_from_row_depth(row, prefix, offset, depth).map($pointer::new)
+                }
+
+                fn is_all_null_depth(
+                    row: &rusqlite::Row,
+                    prefix: Option<&str>,
+                    offset: usize,
+                    depth: usize,
+                ) -> Result<bool, rusqlite::Error> {
+                    T::is_all_null_depth(row, prefix, offset, depth)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_row_for_pointer!(Box, Rc, Arc);
+
+/// A trait that allows mapping a [`rusqlite::Row`] to types that borrow from it.
+///
+/// This is implemented by deriving `FromRow` on a struct with a lifetime, which allows fields
+/// like `&'r str`, `&'r [u8]` and [`ValueRef<'r>`] to be read without copying them out of
+/// SQLite. The borrow is only valid while the row is, for instance inside a `query_map` closure.
+pub trait FromRowRef<'r>: Sized {
+    /// Try's to perform the conversion.
+    ///
+    /// Will return an error if the row does not contain the expected column names.
+    fn try_from_row_ref(row: &'r rusqlite::Row<'_>) -> Result<Self, rusqlite::Error> {
+        Self::try_from_row_ref_prefixed(row, None, 0)
+    }
+
+    /// Try's to perform the conversion. Each row will be extracted using it's name prefixed with
+    /// `prefix`, columns that are mapped by position are read relative to `offset`.
+    ///
+    /// Will return an error if the row does not contain the expected columns.
+    fn try_from_row_ref_prefixed(
+        row: &'r rusqlite::Row<'_>,
+        prefix: Option<&str>,
+        offset: usize,
+    ) -> Result<Self, rusqlite::Error>;
+
+    /// Try's to check if all the columns that are needed by this struct are sql 'null' values.
+    ///
+    /// Will return an error if the row does not contain the expected columns.
+    fn is_all_null_ref(
+        row: &'r rusqlite::Row<'_>,
+        prefix: Option<&str>,
+        offset: usize,
+    ) -> Result<bool, rusqlite::Error>;
+}
+
+impl<'r, T: FromRowRef<'r>> FromRowRef<'r> for Option<T> {
+    fn try_from_row_ref_prefixed(
+        row: &'r rusqlite::Row<'_>,
+        prefix: Option<&str>,
+        offset: usize,
+    ) -> Result<Self, rusqlite::Error> {
+        if T::is_all_null_ref(row, prefix, offset)? {
+            Ok(None)
+        } else {
+            Ok(Some(T::try_from_row_ref_prefixed(row, prefix, offset)?))
+        }
+    }
+
+    fn is_all_null_ref(
+        row: &'r rusqlite::Row<'_>,
+        prefix: Option<&str>,
+        offset: usize,
+    ) -> Result<bool, rusqlite::Error> {
+        T::is_all_null_ref(row, prefix, offset)
+    }
+}
+
+/// A trait for types that can be borrowed from a SQLite value, the counterpart of
+/// [`FromSql`] for fields of a struct that implements [`FromRowRef`].
+pub trait FromSqlRef<'r>: Sized {
+    /// Converts a SQLite value into this type.
+    fn column_result_ref(value: ValueRef<'r>) -> FromSqlResult<Self>;
+}
+
+impl<'r> FromSqlRef<'r> for &'r str {
+    fn column_result_ref(value: ValueRef<'r>) -> FromSqlResult<Self> {
+        value.as_str()
+    }
+}
+
+impl<'r> FromSqlRef<'r> for &'r [u8] {
+    fn column_result_ref(value: ValueRef<'r>) -> FromSqlResult<Self> {
+        value.as_blob()
+    }
+}
+
+impl<'r> FromSqlRef<'r> for ValueRef<'r> {
+    fn column_result_ref(value: ValueRef<'r>) -> FromSqlResult<Self> {
+        Ok(value)
+    }
+}
+
+impl<'r, T: FromSqlRef<'r>> FromSqlRef<'r> for Option<T> {
+    fn column_result_ref(value: ValueRef<'r>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Null => Ok(None),
+            value => T::column_result_ref(value).map(Some),
+        }
+    }
+}
+
+/// Borrows the value of a column using [`FromSqlRef`], like [`rusqlite::Row::get`] does using
+/// [`FromSql`].
+///
+/// Will return an error if the column doesn't exist or the value can't be converted.
+pub fn get_ref<'r, T: FromSqlRef<'r>, I: RowIndex>(
+    row: &'r rusqlite::Row<'_>,
+    idx: I,
+) -> Result<T, rusqlite::Error> {
+    get_with(row, idx, T::column_result_ref)
+}
+
+/// Converts the value of a column using `convert`, like [`rusqlite::Row::get`] does using
+/// [`FromSql`].
+///
+/// Will return an error if the column doesn't exist or the value can't be converted, errors
+/// returned by `convert` are given the context of the column.
+pub fn get_with<'r, T, I: RowIndex>(
+    row: &'r rusqlite::Row<'_>,
+    idx: I,
+    convert: impl FnOnce(ValueRef<'r>) -> FromSqlResult<T>,
+) -> Result<T, rusqlite::Error> {
+    let idx = idx.idx(row.as_ref())?;
+    let value = row.get_ref(idx)?;
+
+    convert(value).map_err(|err| from_sql_error(row, idx, value, err))
+}
+
+/// Converts the value of a column using [`FromSql`] followed by a fallible function, like the
+/// `#[from_row(try_from_fn = "..")]` attribute does.
+///
+/// Will return an error if the column doesn't exist or the value can't be converted, errors
+/// returned by `convert` are wrapped in a [`ConversionError`] that names the column.
+pub fn get_try_from_fn<T: FromSql, U, E, I: RowIndex>(
+    row: &rusqlite::Row,
+    idx: I,
+    convert: impl FnOnce(T) -> Result<U, E>,
+) -> Result<U, rusqlite::Error>
+where
+    E: Error + Send + Sync + 'static,
+{
+    let idx = idx.idx(row.as_ref())?;
+    let value = row.get_ref(idx)?;
+    let base = T::column_result(value).map_err(|err| from_sql_error(row, idx, value, err))?;
+
+    convert(base).map_err(|err| conversion_error(row, idx, value, err))
+}
+
+/// Converts the value of a column using a function that also receives the [`ColumnContext`],
+/// like the `#[from_row(from_value = "..")]` attribute does.
+///
+/// Will return an error if the column doesn't exist or the value can't be converted, errors
+/// returned by `convert` are given the context of the column.
+pub fn get_from_value<'r, T, I: RowIndex>(
+    row: &'r rusqlite::Row<'_>,
+    idx: I,
+    convert: impl FnOnce(ValueRef<'r>, &ColumnContext) -> FromSqlResult<T>,
+) -> Result<T, rusqlite::Error> {
+    let idx = idx.idx(row.as_ref())?;
+    let value = row.get_ref(idx)?;
+
+    let context = ColumnContext {
+        name: row.as_ref().column_name(idx)?,
+        index: idx,
+        #[cfg(feature = "column_decltype")]
+        statement: row.as_ref(),
+    };
+
+    convert(value, &context).map_err(|err| from_sql_error(row, idx, value, err))
+}
+
+/// Information about the column a value is read from, passed to functions specified by
+/// `#[from_row(from_value = "..")]`.
+#[derive(Debug, Clone, Copy)]
+pub struct ColumnContext<'a> {
+    name: &'a str,
+    index: usize,
+    /// The statement of the row, whose declared types are only looked up when asked for.
+    #[cfg(feature = "column_decltype")]
+    statement: &'a rusqlite::Statement<'a>,
+}
+
+impl<'a> ColumnContext<'a> {
+    /// The name of the column.
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// The index of the column.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The declared type of the column, as specified in the table definition.
+    ///
+    /// Returns `None` if the column is an expression or subquery instead of a table column.
+    ///
+    /// This is looked up when called, which reads the declared types of all columns of the
+    /// statement, so call it once per value when needed.
+    #[cfg(feature = "column_decltype")]
+    pub fn decl_type(&self) -> Option<String> {
+        let columns = self.statement.columns();
+
+        columns.get(self.index)?.decl_type().map(str::to_string)
+    }
+}
+
+/// Parses the text of a column using [`FromStr`], like the `#[from_row(parse)]` attribute does.
+///
+/// Will return an error if the column doesn't exist or isn't text, a parse error is wrapped in a
+/// [`ConversionError`] that names the column and includes the text.
+pub fn get_parsed<T: FromStr, I: RowIndex>(
+    row: &rusqlite::Row,
+    idx: I,
+) -> Result<T, rusqlite::Error>
+where
+    T::Err: fmt::Display,
+{
+    let idx = idx.idx(row.as_ref())?;
+    let value = row.get_ref(idx)?;
+    let text = value
+        .as_str()
+        .map_err(|err| from_sql_error(row, idx, value, err))?;
+
+    text.parse()
+        .map_err(|err| conversion_error(row, idx, value, format!("can't parse `{text}`: {err}")))
+}
+
+/// Splits the text of a column by `separator` and parses each element using [`FromStr`], like
+/// the `#[from_row(split = "..")]` attribute does.
+///
+/// An SQL `null` or empty text produces an empty collection. When `trim` is set, whitespace
+/// around each element is removed before parsing.
+///
+/// Will return an error if the column doesn't exist or isn't text, a parse error is wrapped in a
+/// [`ConversionError`] that names the column and includes the element.
+pub fn get_split<C, T, I>(
+    row: &rusqlite::Row,
+    idx: I,
+    separator: &str,
+    trim: bool,
+) -> Result<C, rusqlite::Error>
+where
+    C: FromIterator<T>,
+    T: FromStr,
+    T::Err: fmt::Display,
+    I: RowIndex,
+{
+    let idx = idx.idx(row.as_ref())?;
+    let value = row.get_ref(idx)?;
+
+    let text = match value {
+        ValueRef::Null => "",
+        value => value
+            .as_str()
+            .map_err(|err| from_sql_error(row, idx, value, err))?,
+    };
+
+    if text.is_empty() {
+        return Ok(C::from_iter(std::iter::empty()));
+    }
+
+    text.split(separator)
+        .map(|element| {
+            let element = if trim { element.trim() } else { element };
+
+            element.parse().map_err(|err| {
+                conversion_error(row, idx, value, format!("can't parse `{element}`: {err}"))
+            })
+        })
+        .collect()
+}
+
+/// Converts the value of a column using [`FromSql`], retrying with SQLite-like coercions when the
+/// value is stored with an unexpected type, like the `#[from_row(coerce)]` attribute does.
+///
+/// The following coercions are tried:
+///
+/// - Numeric text, like `'42'` or `' 1.5 '`, to `INTEGER` or `REAL`.
+/// - Boolean text (`t`, `true`, `y`, `yes`, `on` and `f`, `false`, `n`, `no`, `off`, in any
+///   case) to `1` or `0`.
+/// - `REAL` values without a fractional part to `INTEGER`, and `INTEGER` to `REAL`.
+/// - Numbers to `TEXT`, and `BLOB` values that are valid UTF-8 to `TEXT`.
+///
+/// Will return an error if the column doesn't exist or none of the coercions succeed, in which
+/// case the error is wrapped in a [`ConversionError`] that names the column and includes the
+/// value.
+pub fn get_coerced<T: FromSql, I: RowIndex>(
+    row: &rusqlite::Row,
+    idx: I,
+) -> Result<T, rusqlite::Error> {
+    let idx = idx.idx(row.as_ref())?;
+    let value = row.get_ref(idx)?;
+
+    match T::column_result(value) {
+        Err(FromSqlError::InvalidType) if value != ValueRef::Null => coercions(value)
+            .iter()
+            .find_map(|coerced| T::column_result(coerced.into()).ok())
+            .ok_or_else(|| {
+                let err = format!(
+                    "can't coerce {} into `{}`",
+                    describe(value),
+                    type_name::<T>()
+                );
+
+                conversion_error(row, idx, value, err)
+            }),
+        result => result.map_err(|err| from_sql_error(row, idx, value, err)),
+    }
+}
+
+/// Returns the values that `value` can be coerced into, in order of preference.
+fn coercions(value: ValueRef) -> Vec<Value> {
+    match value {
+        ValueRef::Null => Vec::new(),
+        ValueRef::Integer(value) => vec![Value::Real(value as f64), Value::Text(value.to_string())],
+        ValueRef::Real(value) => integral(value)
+            .map(Value::Integer)
+            .into_iter()
+            .chain([Value::Text(value.to_string())])
+            .collect(),
+        ValueRef::Text(text) => {
+            let Ok(text) = std::str::from_utf8(text) else {
+                return Vec::new();
+            };
+            let text = text.trim();
+
+            if let Ok(value) = text.parse::<i64>() {
+                vec![Value::Integer(value), Value::Real(value as f64)]
+            } else if let Some(value) = text.parse::<f64>().ok().filter(|value| value.is_finite()) {
+                integral(value)
+                    .map(Value::Integer)
+                    .into_iter()
+                    .chain([Value::Real(value)])
+                    .collect()
+            } else {
+                match text.to_ascii_lowercase().as_str() {
+                    "t" | "true" | "y" | "yes" | "on" => vec![Value::Integer(1)],
+                    "f" | "false" | "n" | "no" | "off" => vec![Value::Integer(0)],
+                    _ => Vec::new(),
+                }
+            }
+        }
+        ValueRef::Blob(blob) => std::str::from_utf8(blob)
+            .map(|text| vec![Value::Text(text.to_string())])
+            .unwrap_or_default(),
+    }
+}
+
+/// Returns `value` as an integer if it has no fractional part and fits in an `i64`.
+fn integral(value: f64) -> Option<i64> {
+    // The upper bound is exclusive as `i64::MAX` isn't representable as a float.
+    (value.fract() == 0.0 && value >= i64::MIN as f64 && value < i64::MAX as f64)
+        .then_some(value as i64)
+}
+
+/// Describes a value for use in error messages.
+fn describe(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => "NULL".to_string(),
+        ValueRef::Integer(value) => format!("INTEGER `{value}`"),
+        ValueRef::Real(value) => format!("REAL `{value}`"),
+        ValueRef::Text(text) => format!("TEXT `{}`", String::from_utf8_lossy(text)),
+        ValueRef::Blob(blob) => format!("BLOB of {} bytes", blob.len()),
+    }
+}
+
+/// Error returned when the value of a column can't be converted into the type of a field.
+///
+/// This is wrapped in a [`rusqlite::Error::FromSqlConversionFailure`], which holds the index of
+/// the column, and adds the name of the column to the original error.
+#[derive(Debug)]
+pub struct ConversionError {
+    column: String,
+    source: Box<dyn Error + Send + Sync>,
+}
+
+impl ConversionError {
+    /// Creates a new error for the column named `column`.
+    pub fn new(column: impl Into<String>, source: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        Self {
+            column: column.into(),
+            source: source.into(),
+        }
+    }
+
+    /// The name of the column that couldn't be converted.
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid value for column `{}`: {}",
+            self.column, self.source
+        )
+    }
+}
+
+impl Error for ConversionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+/// Returns the name of a column, or its index when the name can't be retrieved.
+fn column_name(row: &rusqlite::Row, idx: usize) -> String {
+    row.as_ref()
+        .column_name(idx)
+        .map_or_else(|_| idx.to_string(), str::to_string)
+}
+
+/// Wraps `err` in a [`ConversionError`] for the column at `idx`.
+fn conversion_error(
+    row: &rusqlite::Row,
+    idx: usize,
+    value: ValueRef,
+    err: impl Into<Box<dyn Error + Send + Sync>>,
+) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(
+        idx,
+        value.data_type(),
+        Box::new(ConversionError::new(column_name(row, idx), err)),
+    )
+}
+
+/// Converts a [`FromSqlError`] into a [`rusqlite::Error`] with the context of the column, the
+/// same way [`rusqlite::Row::get`] does.
+fn from_sql_error(
+    row: &rusqlite::Row,
+    idx: usize,
+    value: ValueRef,
+    err: FromSqlError,
+) -> rusqlite::Error {
+    match err {
+        FromSqlError::InvalidType => {
+            rusqlite::Error::InvalidColumnType(idx, column_name(row, idx), value.data_type())
+        }
+        FromSqlError::OutOfRange(value) => rusqlite::Error::IntegralValueOutOfRange(idx, value),
+        FromSqlError::Other(err) => {
+            rusqlite::Error::FromSqlConversionFailure(idx, value.data_type(), err)
+        }
+        err => rusqlite::Error::FromSqlConversionFailure(idx, value.data_type(), Box::new(err)),
+    }
+}
+
+/// Wraps an error that concerns rows as a whole instead of one of their columns, like a rejected
+/// struct or a duplicate key, in a [`rusqlite::Error::ToSqlConversionFailure`], the variant that holds an arbitrary
+/// error without the index of a column.
+fn row_error(err: impl Error + Send + Sync + 'static) -> rusqlite::Error {
+    rusqlite::Error::ToSqlConversionFailure(Box::new(err))
+}
+
+/// Error returned when a function specified by `#[from_row(validate = "..")]` rejects a
+/// struct.
+///
+/// This is returned as a [`rusqlite::Error::ToSqlConversionFailure`], since it doesn't concern a
+/// single column.
+#[derive(Debug)]
+pub struct ValidationError {
+    type_name: &'static str,
+    source: Box<dyn Error + Send + Sync>,
+}
+
+impl ValidationError {
+    /// Creates a new error for the struct named `type_name`.
+    pub fn new(type_name: &'static str, source: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        Self {
+            type_name,
+            source: source.into(),
+        }
+    }
+
+    /// The name of the struct that failed validation.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "validation of `{}` failed: {}",
+            self.type_name, self.source
+        )
+    }
+}
+
+impl Error for ValidationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+impl From<ValidationError> for rusqlite::Error {
+    fn from(err: ValidationError) -> Self {
+        row_error(err)
     }
 }
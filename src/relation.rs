@@ -0,0 +1,109 @@
+//! Batched loading of related rows, which avoids issuing a query for every parent (N+1 queries)
+//! or multiplying rows using a join.
+//!
+//! The children of all parents are selected using a query containing `{keys}`, which is replaced
+//! by numbered placeholders for the keys of the parents, like
+//! `SELECT * FROM comments WHERE todo_id IN ({keys}) ORDER BY id`.
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use rusqlite::{types::ToSql, Connection};
+
+use crate::FromRow;
+
+/// The marker that is replaced by the placeholders of the keys.
+const KEYS: &str = "{keys}";
+
+/// The maximum number of keys bound to a single query, which is below the default maximum
+/// number of parameters of older SQLite versions. More keys are loaded using multiple queries.
+const CHUNK_SIZE: usize = 999;
+
+/// Loads the children of `parents` using a single query for up to 999 keys, returning them
+/// grouped by the key of their parent.
+///
+/// `key` extracts the key of a parent, which is bound in place of `{keys}` in `sql`. The keys are
+/// bound once as numbered parameters, so `{keys}` may appear more than once. Each row is
+/// mapped using [`FromRow`] and grouped using `child_key`, which returns the key of the parent
+/// of a child. Children are in the order returned by the query, and parents without children
+/// are absent from the map.
+///
+/// # Panics
+///
+/// Panics if `sql` doesn't contain `{keys}`.
+pub fn load_related<P, K, C>(
+    connection: &Connection,
+    sql: &str,
+    parents: &[P],
+    key: impl Fn(&P) -> K,
+    child_key: impl Fn(&C) -> K,
+) -> Result<HashMap<K, Vec<C>>, rusqlite::Error>
+where
+    K: ToSql + Eq + Hash,
+    C: FromRow,
+{
+    assert!(sql.contains(KEYS), "query should contain `{KEYS}`");
+
+    let keys = parents
+        .iter()
+        .map(key)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    let mut related = HashMap::<K, Vec<C>>::new();
+
+    for chunk in keys.chunks(CHUNK_SIZE) {
+        let placeholders = (1..=chunk.len())
+            .map(|index| format!("?{index}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        // Not cached, as the statement differs for every number of keys.
+        let mut statement = connection.prepare(&sql.replace(KEYS, &placeholders))?;
+        let children = statement.query_map(rusqlite::params_from_iter(chunk), C::try_from_row)?;
+
+        for child in children {
+            let child = child?;
+
+            related.entry(child_key(&child)).or_default().push(child);
+        }
+    }
+
+    Ok(related)
+}
+
+/// Loads the children of `parents` like [`load_related`] does, and passes them to `set`
+/// together with their parent, for instance to assign a field marked with
+/// `#[from_row(skip)]`.
+///
+/// Parents without children receive an empty `Vec`. When multiple parents have the same key,
+/// only the first one receives the children.
+///
+/// # Panics
+///
+/// Panics if `sql` doesn't contain `{keys}`.
+pub fn attach_related<P, K, C>(
+    connection: &Connection,
+    sql: &str,
+    parents: &mut [P],
+    key: impl Fn(&P) -> K,
+    child_key: impl Fn(&C) -> K,
+    mut set: impl FnMut(&mut P, Vec<C>),
+) -> Result<(), rusqlite::Error>
+where
+    K: ToSql + Eq + Hash,
+    C: FromRow,
+{
+    let mut related = load_related(connection, sql, parents, &key, child_key)?;
+
+    for parent in parents {
+        let children = related.remove(&key(parent)).unwrap_or_default();
+
+        set(parent, children);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! Statements with a `RETURNING` clause, which is supported since SQLite 3.35, whose returned
+//! rows are mapped using [`FromRow`].
+//!
+//! This reads values assigned by the database, like generated ids, defaults and timestamps,
+//! without another query.
+
+use rusqlite::{Connection, Params};
+
+use crate::FromRow;
+
+/// Extends [`Connection`] with statements that map their returned rows using [`FromRow`].
+pub trait ConnectionExt {
+    /// Executes a statement that returns a single row, like an `INSERT` of a single row with
+    /// `RETURNING *`, and maps the returned row.
+    ///
+    /// Will return [`rusqlite::Error::QueryReturnedNoRows`] if no row was returned, further rows
+    /// are ignored.
+    fn insert_returning<T: FromRow, P: Params>(
+        &self,
+        sql: &str,
+        params: P,
+    ) -> Result<T, rusqlite::Error>;
+
+    /// Executes a statement with a `RETURNING` clause, like an `UPDATE` or `DELETE` of multiple
+    /// rows, and maps all returned rows.
+    fn execute_returning_all<T: FromRow, P: Params>(
+        &self,
+        sql: &str,
+        params: P,
+    ) -> Result<Vec<T>, rusqlite::Error>;
+}
+
+impl ConnectionExt for Connection {
+    fn insert_returning<T: FromRow, P: Params>(
+        &self,
+        sql: &str,
+        params: P,
+    ) -> Result<T, rusqlite::Error> {
+        self.prepare_cached(sql)?.query_row(params, T::try_from_row)
+    }
+
+    fn execute_returning_all<T: FromRow, P: Params>(
+        &self,
+        sql: &str,
+        params: P,
+    ) -> Result<Vec<T>, rusqlite::Error> {
+        let mut statement = self.prepare_cached(sql)?;
+        let rows = statement.query_map(params, T::try_from_row)?;
+
+        rows.collect()
+    }
+}
@@ -0,0 +1,371 @@
+//! Statements for structs that correspond to a table, implemented using `#[derive(Table)]`.
+//!
+//! The statements read and write the columns of the fields that aren't skipped, named like
+//! [`FromRow`] names them, and identify rows by the field marked as `#[table(primary_key)]`.
+
+use std::ops::{Deref, DerefMut};
+
+use rusqlite::{
+    types::{ToSql, ToSqlOutput, Value},
+    Connection, OptionalExtension,
+};
+
+use crate::FromRow;
+
+/// A struct that is stored as a row of a table.
+///
+/// Besides the constants and values, which are implemented by the derive macro, this provides
+/// the common statements on the table.
+pub trait Table: FromRow {
+    /// The name of the table.
+    const NAME: &'static str;
+
+    /// The names of the columns, in the order of [`Table::values`].
+    const COLUMNS: &'static [&'static str];
+
+    /// The name of the primary key column, which is one of the [`Table::COLUMNS`].
+    const PRIMARY_KEY: &'static str;
+
+    /// Returns the `CREATE TABLE` statement of the table.
+    ///
+    /// The type of each column is derived from the type of its field, like `INTEGER` for `i64`,
+    /// `TEXT` for `String` and `BLOB` for `Vec<u8>`, and columns are `NOT NULL` unless the field
+    /// is an `Option`.
+    fn create_table_sql() -> String;
+
+    /// The value of the primary key, converted like [`Table::values`].
+    fn primary_key(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error>;
+
+    /// The values of the columns, in the order of [`Table::COLUMNS`].
+    ///
+    /// Fields are converted using `ToSql`, or using the `to_sql` function of the module of a field
+    /// with `#[from_row(with = "..")]`.
+    fn values(&self) -> Result<Vec<ToSqlOutput<'_>>, rusqlite::Error>;
+
+    /// Inserts this row, returning the number of inserted rows.
+    ///
+    /// A `NULL` primary key, like an `Option` that is `None`, lets SQLite assign it for an
+    /// `INTEGER PRIMARY KEY` column.
+    fn insert(&self, connection: &Connection) -> Result<usize, rusqlite::Error> {
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote(Self::NAME),
+            column_list(Self::COLUMNS),
+            placeholders(Self::COLUMNS.len()),
+        );
+
+        connection
+            .prepare_cached(&sql)?
+            .execute(rusqlite::params_from_iter(self.values()?))
+    }
+
+    /// Inserts this row and returns it as stored, including the values assigned by the database,
+    /// like a generated primary key or column defaults.
+    ///
+    /// Requires SQLite 3.35 or later, which supports `RETURNING`.
+    fn insert_returning(&self, connection: &Connection) -> Result<Self, rusqlite::Error> {
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({}) RETURNING {}",
+            quote(Self::NAME),
+            column_list(Self::COLUMNS),
+            placeholders(Self::COLUMNS.len()),
+            column_list(Self::COLUMNS),
+        );
+
+        connection.prepare_cached(&sql)?.query_row(
+            rusqlite::params_from_iter(self.values()?),
+            Self::try_from_row,
+        )
+    }
+
+    /// Selects the row with the primary key `id`, if any.
+    fn get_by_id(connection: &Connection, id: impl ToSql) -> Result<Option<Self>, rusqlite::Error> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE {} = ?1",
+            column_list(Self::COLUMNS),
+            quote(Self::NAME),
+            quote(Self::PRIMARY_KEY),
+        );
+
+        connection
+            .prepare_cached(&sql)?
+            .query_row([id], Self::try_from_row)
+            .optional()
+    }
+
+    /// Selects all rows of the table.
+    fn find_all(connection: &Connection) -> Result<Vec<Self>, rusqlite::Error> {
+        let sql = format!(
+            "SELECT {} FROM {}",
+            column_list(Self::COLUMNS),
+            quote(Self::NAME),
+        );
+
+        let mut statement = connection.prepare_cached(&sql)?;
+        let rows = statement.query_map([], Self::try_from_row)?;
+
+        rows.collect()
+    }
+
+    /// Updates all columns of the row with the primary key of this row, returning the number of
+    /// updated rows. No statement is executed when the primary key is the only column.
+    fn update(&self, connection: &Connection) -> Result<usize, rusqlite::Error> {
+        let (columns, mut values): (Vec<&str>, Vec<_>) = Self::COLUMNS
+            .iter()
+            .zip(self.values()?)
+            .filter(|(column, _)| **column != Self::PRIMARY_KEY)
+            .unzip();
+
+        if columns.is_empty() {
+            return Ok(0);
+        }
+
+        let assignments = columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| format!("{} = ?{}", quote(column), index + 1))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let sql = format!(
+            "UPDATE {} SET {assignments} WHERE {} = ?{}",
+            quote(Self::NAME),
+            quote(Self::PRIMARY_KEY),
+            columns.len() + 1,
+        );
+
+        values.push(self.primary_key()?);
+
+        connection
+            .prepare_cached(&sql)?
+            .execute(rusqlite::params_from_iter(values))
+    }
+
+    /// Deletes the row with the primary key of this row, returning the number of deleted rows.
+    fn delete(&self, connection: &Connection) -> Result<usize, rusqlite::Error> {
+        let sql = format!(
+            "DELETE FROM {} WHERE {} = ?1",
+            quote(Self::NAME),
+            quote(Self::PRIMARY_KEY),
+        );
+
+        connection
+            .prepare_cached(&sql)?
+            .execute([self.primary_key()?])
+    }
+
+    /// Inserts this row, or updates all columns of the row with the same primary key, returning
+    /// the number of changed rows.
+    fn upsert(&self, connection: &Connection) -> Result<usize, rusqlite::Error> {
+        let assignments = Self::COLUMNS
+            .iter()
+            .filter(|column| **column != Self::PRIMARY_KEY)
+            .map(|column| format!("{0} = excluded.{0}", quote(column)))
+            .collect::<Vec<_>>();
+
+        let conflict = if assignments.is_empty() {
+            "NOTHING".to_string()
+        } else {
+            format!("UPDATE SET {}", assignments.join(", "))
+        };
+
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT ({}) DO {conflict}",
+            quote(Self::NAME),
+            column_list(Self::COLUMNS),
+            placeholders(Self::COLUMNS.len()),
+            quote(Self::PRIMARY_KEY),
+        );
+
+        connection
+            .prepare_cached(&sql)?
+            .execute(rusqlite::params_from_iter(self.values()?))
+    }
+}
+
+/// A row that records the values of its columns when it's read, so only the changed columns are
+/// written by [`Tracked::update`].
+///
+/// This is read using [`FromRow`] like the row itself, and dereferences to it.
+#[derive(Debug, Clone)]
+pub struct Tracked<T> {
+    value: T,
+    /// The values of the columns when last read or updated.
+    original: Vec<Value>,
+}
+
+impl<T: Table> Tracked<T> {
+    /// Starts tracking the changes to `value`, as if it was just read.
+    ///
+    /// Will return an error if the value of a column can't be converted using `ToSql` or
+    /// compared, like a zero filled blob.
+    pub fn new(value: T) -> Result<Self, rusqlite::Error> {
+        let original = snapshot(&value)?;
+
+        Ok(Self { value, original })
+    }
+
+    /// Returns the names of the columns that have changed since the row was read or updated.
+    pub fn changed_columns(&self) -> Result<Vec<&'static str>, rusqlite::Error> {
+        Ok(self
+            .changes()?
+            .into_iter()
+            .map(|(column, _)| column)
+            .collect())
+    }
+
+    /// Updates the changed columns of the row with the primary key this row was read with,
+    /// returning the number of updated rows. No statement is executed when nothing has changed.
+    ///
+    /// The row is tracked again from its current values after updating.
+    pub fn update(&mut self, connection: &Connection) -> Result<usize, rusqlite::Error> {
+        let changes = self.changes()?;
+
+        if changes.is_empty() {
+            return Ok(0);
+        }
+
+        let assignments = changes
+            .iter()
+            .enumerate()
+            .map(|(index, (column, _))| format!("{} = ?{}", quote(column), index + 1))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let sql = format!(
+            "UPDATE {} SET {assignments} WHERE {} = ?{}",
+            quote(T::NAME),
+            quote(T::PRIMARY_KEY),
+            changes.len() + 1,
+        );
+
+        let primary_key = T::COLUMNS
+            .iter()
+            .position(|column| *column == T::PRIMARY_KEY)
+            .expect("primary key should be a column");
+
+        let mut values = changes
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect::<Vec<_>>();
+
+        // The primary key itself might have changed.
+        values.push(self.original[primary_key].clone());
+
+        let updated = connection
+            .prepare_cached(&sql)?
+            .execute(rusqlite::params_from_iter(values))?;
+
+        self.original = snapshot(&self.value)?;
+
+        Ok(updated)
+    }
+
+    /// Stops tracking the changes, returning the row.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// Returns the columns that have changed together with their current value.
+    fn changes(&self) -> Result<Vec<(&'static str, Value)>, rusqlite::Error> {
+        let mut changes = Vec::new();
+
+        for ((column, value), original) in T::COLUMNS
+            .iter()
+            .zip(self.value.values()?)
+            .zip(&self.original)
+        {
+            let value = to_value(column, value)?;
+
+            if value != *original {
+                changes.push((*column, value));
+            }
+        }
+
+        Ok(changes)
+    }
+}
+
+impl<T: Table> FromRow for Tracked<T> {
+    fn try_from_row_prefixed(
+        row: &rusqlite::Row,
+        prefix: Option<&str>,
+    ) -> Result<Self, rusqlite::Error> {
+        Self::new(T::try_from_row_prefixed(row, prefix)?)
+    }
+
+    fn try_from_row_offset(
+        row: &rusqlite::Row,
+        prefix: Option<&str>,
+        offset: usize,
+    ) -> Result<Self, rusqlite::Error> {
+        Self::new(T::try_from_row_offset(row, prefix, offset)?)
+    }
+
+    fn is_all_null(row: &rusqlite::Row, prefix: Option<&str>) -> Result<bool, rusqlite::Error> {
+        T::is_all_null(row, prefix)
+    }
+
+    fn is_all_null_offset(
+        row: &rusqlite::Row,
+        prefix: Option<&str>,
+        offset: usize,
+    ) -> Result<bool, rusqlite::Error> {
+        T::is_all_null_offset(row, prefix, offset)
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Tracked<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+/// Returns the current values of the columns of `value`.
+fn snapshot<T: Table>(value: &T) -> Result<Vec<Value>, rusqlite::Error> {
+    T::COLUMNS
+        .iter()
+        .zip(value.values()?)
+        .map(|(column, value)| to_value(column, value))
+        .collect()
+}
+
+/// Converts the output of `ToSql` to a value that can be compared, which isn't possible for
+/// outputs like zero filled blobs.
+fn to_value(column: &str, output: ToSqlOutput<'_>) -> Result<Value, rusqlite::Error> {
+    match output {
+        ToSqlOutput::Borrowed(value) => Ok(value.into()),
+        ToSqlOutput::Owned(value) => Ok(value),
+        _ => Err(rusqlite::Error::ToSqlConversionFailure(
+            format!("can't track the value of column `{column}`").into(),
+        )),
+    }
+}
+
+/// Quotes a table or column name, so it can't be confused with a keyword.
+pub fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn column_list(columns: &[&str]) -> String {
+    columns
+        .iter()
+        .map(|column| quote(column))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn placeholders(count: usize) -> String {
+    (1..=count)
+        .map(|index| format!("?{index}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,169 @@
+//! Assembly of trees from flat rows, like the results of a `WITH RECURSIVE` query, using the
+//! `#[from_row(key)]`, `#[from_row(parent_key)]` and `#[from_row(children)]` attributes.
+
+use std::{collections::HashMap, error::Error, fmt, hash::Hash};
+
+use crate::Keyed;
+
+/// A node of a tree that is stored as rows referring to their parent, which is identified by its
+/// [`Keyed::key`].
+///
+/// This is implemented by the derive macro for structs with fields marked as `key`,
+/// `parent_key` and `children`.
+pub trait TreeNode: Keyed + Sized {
+    /// The key of the parent of this node, or `None` for a root.
+    fn parent_key(&self) -> Option<&Self::Key>;
+
+    /// The children of this node, which are added by [`build_tree`].
+    fn children_mut(&mut self) -> &mut Vec<Self>;
+}
+
+/// Assembles nodes into trees, returning the roots.
+///
+/// Nodes can be given in any order, the order of the roots and of the children of each node is
+/// the order in which they were given. Accepts the result of `query_map` directly.
+///
+/// Will return an error if a row couldn't be read, a key is used more than once, a node refers to
+/// a parent that doesn't exist, or nodes refer to each other in a cycle.
+pub fn build_tree<T: TreeNode>(
+    rows: impl IntoIterator<Item = Result<T, rusqlite::Error>>,
+) -> Result<Vec<T>, TreeError<T::Key>>
+where
+    T::Key: Eq + Hash + Clone,
+{
+    let mut nodes = Vec::new();
+    let mut indices = HashMap::new();
+
+    for row in rows {
+        let node = row?;
+
+        if indices.insert(node.key().clone(), nodes.len()).is_some() {
+            return Err(TreeError::DuplicateKey(node.key().clone()));
+        }
+
+        nodes.push(Some(node));
+    }
+
+    let mut roots = Vec::new();
+    let mut children = vec![Vec::new(); nodes.len()];
+    let mut parents = vec![None; nodes.len()];
+
+    for (index, node) in nodes.iter().flatten().enumerate() {
+        match node.parent_key() {
+            None => roots.push(index),
+            Some(parent) => match indices.get(parent) {
+                Some(&parent) => {
+                    children[parent].push(index);
+                    parents[index] = Some(parent);
+                }
+                None => {
+                    return Err(TreeError::Orphan {
+                        key: node.key().clone(),
+                        parent: parent.clone(),
+                    })
+                }
+            },
+        }
+    }
+
+    // Every node has a parent, so nodes that can't be reached from a root are part of a cycle.
+    let mut order = roots.clone();
+    let mut visited = 0;
+
+    while let Some(&index) = order.get(visited) {
+        order.extend(&children[index]);
+        visited += 1;
+    }
+
+    if order.len() < nodes.len() {
+        let mut reachable = vec![false; nodes.len()];
+
+        for &index in &order {
+            reachable[index] = true;
+        }
+
+        let mut index = reachable
+            .iter()
+            .position(|reachable| !reachable)
+            .expect("should have an unreachable node");
+
+        // The unreachable node might only descend from a cycle, so follow its parents until one
+        // repeats, which is part of the cycle.
+        let mut seen = vec![false; nodes.len()];
+
+        while !seen[index] {
+            seen[index] = true;
+            index = parents[index].expect("unreachable nodes should have a parent");
+        }
+
+        let key = nodes[index].as_ref().expect("should be present").key();
+
+        return Err(TreeError::Cycle(key.clone()));
+    }
+
+    // Parents come before their children, so complete the children first.
+    let mut complete: Vec<Option<T>> = nodes.iter().map(|_| None).collect();
+
+    for &index in order.iter().rev() {
+        let mut node = nodes[index].take().expect("should be visited once");
+
+        for &child in &children[index] {
+            let child = complete[child].take().expect("should be complete");
+            node.children_mut().push(child);
+        }
+
+        complete[index] = Some(node);
+    }
+
+    Ok(roots
+        .into_iter()
+        .map(|index| complete[index].take().expect("should be complete"))
+        .collect())
+}
+
+/// Error returned by [`build_tree`].
+#[derive(Debug)]
+pub enum TreeError<K> {
+    /// A row couldn't be read.
+    Sqlite(rusqlite::Error),
+    /// More than one node has this key.
+    DuplicateKey(K),
+    /// A node refers to a parent that doesn't exist.
+    Orphan {
+        /// The key of the node.
+        key: K,
+        /// The key of the missing parent.
+        parent: K,
+    },
+    /// Nodes refer to each other as parent, so they can't be reached from a root. Holds the key
+    /// of one of the nodes in the cycle.
+    Cycle(K),
+}
+
+impl<K> From<rusqlite::Error> for TreeError<K> {
+    fn from(err: rusqlite::Error) -> Self {
+        TreeError::Sqlite(err)
+    }
+}
+
+impl<K: fmt::Debug> fmt::Display for TreeError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TreeError::Sqlite(err) => err.fmt(f),
+            TreeError::DuplicateKey(key) => write!(f, "more than one node has key `{key:?}`"),
+            TreeError::Orphan { key, parent } => {
+                write!(f, "node `{key:?}` refers to missing parent `{parent:?}`")
+            }
+            TreeError::Cycle(key) => write!(f, "node `{key:?}` is part of a cycle"),
+        }
+    }
+}
+
+impl<K: fmt::Debug> Error for TreeError<K> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TreeError::Sqlite(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -1,7 +1,17 @@
-use std::{ffi::OsStr, marker::PhantomData, path::PathBuf};
+use std::{
+    ffi::OsStr,
+    marker::PhantomData,
+    net::IpAddr,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use rusqlite::{params, Connection};
-use rusqlite_from_row::FromRow;
+use rusqlite::{
+    params,
+    types::{FromSqlError, FromSqlResult, ValueRef},
+    Connection,
+};
+use rusqlite_from_row::{ColumnContext, FromRow, FromRowRef};
 
 #[derive(Debug, FromRow)]
 pub struct Todo {
@@ -142,3 +152,1991 @@ fn from_row() {
     assert_eq!(todo.views, 0);
     assert_eq!(todo.file.file_name(), Some(OsStr::new("bar.txt")));
 }
+
+#[derive(Debug, FromRow)]
+pub struct TodoStats {
+    #[from_row(index = 0)]
+    count: i64,
+    #[from_row(index = 1)]
+    max_views: Option<i32>,
+    label: String,
+    #[from_row(flatten, index = 3)]
+    range: Option<Range>,
+}
+
+#[derive(Debug, FromRow, PartialEq, Eq)]
+pub struct Range {
+    #[from_row(index = 0)]
+    start: i32,
+    #[from_row(index = 1)]
+    end: i32,
+}
+
+#[test]
+fn from_row_index() {
+    let connection = Connection::open_in_memory().unwrap();
+
+    let stats = connection
+        .query_row(
+            "SELECT count(*), max(v), 'views' AS label, 1, 5 FROM (SELECT 1 AS v UNION SELECT 3)",
+            params![],
+            TodoStats::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(stats.count, 2);
+    assert_eq!(stats.max_views, Some(3));
+    assert_eq!(stats.label, "views");
+    assert_eq!(stats.range, Some(Range { start: 1, end: 5 }));
+
+    let stats = connection
+        .query_row(
+            "SELECT count(*), max(v), 'views' AS label, null, null FROM (SELECT 1 AS v WHERE 0)",
+            params![],
+            TodoStats::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(stats.count, 0);
+    assert_eq!(stats.max_views, None);
+    assert_eq!(stats.range, None);
+}
+
+#[derive(Debug, FromRow)]
+#[from_row(rename_all = "camelCase", prefix_separator = ".")]
+pub struct Post {
+    post_id: i32,
+    #[from_row(flatten, prefix)]
+    main_author: Author,
+    #[from_row(flatten, prefix, prefix_separator = "__")]
+    last_editor: Author,
+}
+
+#[derive(Debug, FromRow)]
+#[from_row(rename_all = "camelCase")]
+pub struct Author {
+    display_name: String,
+    #[from_row(rename = "is_admin")]
+    admin: bool,
+}
+
+#[test]
+fn from_row_prefix_separator() {
+    let connection = Connection::open_in_memory().unwrap();
+
+    let post = connection
+        .query_row(
+            r#"
+            SELECT
+                1 AS postId,
+                'john' AS "mainAuthor.displayName",
+                1 AS "mainAuthor.is_admin",
+                'jack' AS lastEditor__displayName,
+                0 AS lastEditor__is_admin
+            "#,
+            params![],
+            Post::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(post.post_id, 1);
+    assert_eq!(post.main_author.display_name, "john");
+    assert!(post.main_author.admin);
+    assert_eq!(post.last_editor.display_name, "jack");
+    assert!(!post.last_editor.admin);
+
+    // Without a separator, the prefix derived from the field name ends with `_`.
+    let todo = connection
+        .query_row(
+            "
+            SELECT
+                1 AS id,
+                'laundry' AS text,
+                null AS views,
+                'foo/bar.txt' AS file,
+                1 AS author_id,
+                'john' AS author_name,
+                2 AS author_role_id,
+                'admin' AS author_role_kind,
+                3 AS editor_id,
+                'jack' AS editor_name,
+                null AS editor_role_id,
+                null AS editor_role_kind,
+                null AS is_done",
+            params![],
+            Todo::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(todo.author.name, "john");
+    assert_eq!(
+        todo.author.role.map(|role| (role.id, role.kind)),
+        Some((2, "admin".to_string()))
+    );
+    assert_eq!(todo.editor.name, "jack");
+    assert!(todo.editor.role.is_none());
+}
+
+#[derive(Debug, FromRow)]
+#[from_row(borrow)]
+pub struct Document<'r> {
+    id: i32,
+    title: &'r str,
+    body: Option<&'r [u8]>,
+    raw: ValueRef<'r>,
+    #[from_row(flatten, prefix)]
+    owner: Option<Owner<'r>>,
+    #[from_row(flatten, prefix = "role_")]
+    role: Option<Role>,
+}
+
+#[derive(Debug, FromRow)]
+#[from_row(borrow)]
+pub struct Owner<'r> {
+    name: &'r str,
+}
+
+#[test]
+fn from_row_ref() {
+    let connection = Connection::open_in_memory().unwrap();
+
+    let mut statement = connection
+        .prepare(
+            "
+            SELECT 1 AS id, 'foo' AS title, x'0102' AS body, 2.5 AS raw, 'john' AS owner_name, 1 AS role_id, 'admin' AS role_kind
+            UNION ALL
+            SELECT 2, 'bar', null, null, null, null, null
+            ",
+        )
+        .unwrap();
+
+    let titles = statement
+        .query_map(params![], |row| {
+            let document = Document::try_from_row_ref(row)?;
+
+            assert_eq!(
+                document.raw.as_f64_or_null().unwrap().is_some(),
+                document.id == 1
+            );
+
+            Ok(format!(
+                "{}:{}:{:?}:{:?}:{}",
+                document.id,
+                document.title,
+                document.body,
+                document.owner.map(|owner| owner.name),
+                document.role.is_some()
+            ))
+        })
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(
+        titles,
+        [
+            "1:foo:Some([1, 2]):Some(\"john\"):true",
+            "2:bar:None:None:false"
+        ]
+    );
+}
+
+#[test]
+fn from_row_lifetime() {
+    // Structs with lifetimes implement `FromRow` unless they are marked with `borrow`.
+    #[derive(FromRow)]
+    struct Tagged<'a, 'b> {
+        id: i64,
+        #[from_row(skip)]
+        marker: PhantomData<(&'a (), &'b ())>,
+    }
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    let tagged = connection
+        .query_row("SELECT 1 AS id", params![], Tagged::try_from_row)
+        .unwrap();
+
+    assert_eq!(tagged.id, 1);
+    assert_eq!(tagged.marker, PhantomData);
+}
+
+#[derive(Debug, FromRow)]
+#[from_row(after = "Self::normalize", validate = "Self::check")]
+pub struct Event {
+    email: String,
+    start: i32,
+    end: i32,
+}
+
+impl Event {
+    fn normalize(&mut self) {
+        self.email = self.email.to_lowercase();
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.start <= self.end {
+            Ok(())
+        } else {
+            Err(format!("{} is after {}", self.start, self.end))
+        }
+    }
+}
+
+#[test]
+fn from_row_hooks() {
+    let connection = Connection::open_in_memory().unwrap();
+
+    let event = connection
+        .query_row(
+            "SELECT 'John@Example.com' AS email, 1 AS start, 2 AS end",
+            params![],
+            Event::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(event.email, "john@example.com");
+    assert_eq!((event.start, event.end), (1, 2));
+
+    let err = connection
+        .query_row(
+            "SELECT 'john@example.com' AS email, 3 AS start, 2 AS end",
+            params![],
+            Event::try_from_row,
+        )
+        .unwrap_err();
+
+    assert!(matches!(err, rusqlite::Error::ToSqlConversionFailure(_)));
+    assert_eq!(
+        err.to_string(),
+        "validation of `Event` failed: 3 is after 2"
+    );
+}
+
+pub trait Entity {}
+
+#[allow(dead_code)]
+pub struct Id<T> {
+    value: i64,
+    entity: PhantomData<T>,
+}
+
+impl<T: Entity> From<i64> for Id<T> {
+    fn from(value: i64) -> Self {
+        Self {
+            value,
+            entity: PhantomData,
+        }
+    }
+}
+
+impl Entity for Todo {}
+
+struct Private<T>(T);
+
+#[derive(FromRow)]
+#[allow(dead_code)]
+pub struct Reference<T> {
+    #[from_row(from = "i64")]
+    id: Id<T>,
+    #[from_row(skip, bound = "")]
+    marker: PhantomData<T>,
+    #[from_row(from = "String", bound = "")]
+    note: Private<String>,
+}
+
+impl From<String> for Private<String> {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+#[derive(FromRow)]
+#[allow(dead_code)]
+#[from_row(bound = "T: rusqlite::types::FromSql")]
+pub struct Wrapper<T> {
+    value: T,
+    #[from_row(skip)]
+    marker: PhantomData<fn() -> T>,
+}
+
+#[test]
+fn from_row_bound() {
+    let connection = Connection::open_in_memory().unwrap();
+
+    let reference = connection
+        .query_row(
+            "SELECT 42 AS id, 'note' AS note",
+            params![],
+            Reference::<Todo>::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(reference.id.value, 42);
+    assert_eq!(reference.note.0, "note");
+
+    let wrapper = connection
+        .query_row(
+            "SELECT 'foo' AS value",
+            params![],
+            Wrapper::<String>::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(wrapper.value, "foo");
+}
+
+mod unix_seconds {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use rusqlite::types::{FromSql, FromSqlResult, ValueRef};
+
+    pub fn from_sql(value: ValueRef<'_>) -> FromSqlResult<SystemTime> {
+        Ok(UNIX_EPOCH + Duration::from_secs(u64::column_result(value)?))
+    }
+}
+
+mod hex {
+    use rusqlite::types::{FromSqlError, FromSqlResult, ValueRef};
+
+    pub fn from_sql(value: ValueRef<'_>) -> FromSqlResult<[u8; 2]> {
+        let text = value.as_str()?;
+        let byte = |i: usize| {
+            text.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| FromSqlError::Other(format!("invalid hex `{text}`").into()))
+        };
+
+        if text.len() != 4 {
+            return Err(FromSqlError::InvalidBlobSize {
+                expected_size: 2,
+                blob_size: text.len() / 2,
+            });
+        }
+
+        Ok([byte(0)?, byte(2)?])
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct Upload {
+    #[from_row(with = "unix_seconds")]
+    created: SystemTime,
+    #[from_row(with = "unix_seconds")]
+    deleted: Option<SystemTime>,
+    #[from_row(with = "hex")]
+    checksum: [u8; 2],
+}
+
+#[test]
+fn from_row_with() {
+    let connection = Connection::open_in_memory().unwrap();
+
+    let upload = connection
+        .query_row(
+            "SELECT 60 AS created, null AS deleted, 'beef' AS checksum",
+            params![],
+            Upload::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(upload.created, UNIX_EPOCH + Duration::from_secs(60));
+    assert_eq!(upload.deleted, None);
+    assert_eq!(upload.checksum, [0xbe, 0xef]);
+
+    let err = connection
+        .query_row(
+            "SELECT 60 AS created, 'tomorrow' AS deleted, 'beef' AS checksum",
+            params![],
+            Upload::try_from_row,
+        )
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        rusqlite::Error::InvalidColumnType(1, ref name, rusqlite::types::Type::Text) if name == "deleted"
+    ));
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Email(String);
+
+#[derive(Debug)]
+pub struct InvalidEmail;
+
+impl std::fmt::Display for InvalidEmail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("missing `@`")
+    }
+}
+
+impl std::error::Error for InvalidEmail {}
+
+fn parse_email(value: String) -> Result<Email, InvalidEmail> {
+    if value.contains('@') {
+        Ok(Email(value))
+    } else {
+        Err(InvalidEmail)
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct Subscriber {
+    #[from_row(try_from_fn = "parse_email")]
+    email: Email,
+    #[from_row(try_from_fn = "<u8 as TryFrom<i64>>::try_from", default)]
+    age: u8,
+}
+
+#[test]
+fn from_row_try_from_fn() {
+    let connection = Connection::open_in_memory().unwrap();
+
+    let subscriber = connection
+        .query_row(
+            "SELECT 'john@example.com' AS email, null AS age",
+            params![],
+            Subscriber::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(subscriber.email, Email("john@example.com".to_string()));
+    assert_eq!(subscriber.age, 0);
+
+    let err = connection
+        .query_row(
+            "SELECT 'john' AS email, 30 AS age",
+            params![],
+            Subscriber::try_from_row,
+        )
+        .unwrap_err();
+
+    let rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, err) = err else {
+        panic!("unexpected error: {err:?}");
+    };
+
+    assert_eq!(
+        err.to_string(),
+        "invalid value for column `email`: missing `@`"
+    );
+
+    let err = connection
+        .query_row(
+            "SELECT 'john@example.com' AS email, 300 AS age",
+            params![],
+            Subscriber::try_from_row,
+        )
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Integer, _)
+    ));
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DbId(i32);
+
+impl From<i32> for DbId {
+    fn from(value: i32) -> Self {
+        Self(value)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Positive(i64);
+
+impl TryFrom<i64> for Positive {
+    type Error = rusqlite::Error;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        if value > 0 {
+            Ok(Self(value))
+        } else {
+            Err(rusqlite::Error::IntegralValueOutOfRange(usize::MAX, value))
+        }
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct Assignment {
+    #[from_row(from = "i32")]
+    author: Option<DbId>,
+    #[from_row(from = "i32")]
+    reviewer: Option<DbId>,
+    #[from_row(try_from = "i64")]
+    priority: Option<Positive>,
+    #[from_row(from_fn = "<PathBuf as From<String>>::from")]
+    attachment: Option<PathBuf>,
+    #[from_row(try_from_fn = "parse_email")]
+    contact: Option<Email>,
+    #[from_row(from_fn = "path_or_default", no_lift)]
+    fallback: Option<PathBuf>,
+}
+
+fn path_or_default(path: Option<String>) -> Option<PathBuf> {
+    Some(PathBuf::from(
+        path.unwrap_or_else(|| "default.txt".to_string()),
+    ))
+}
+
+#[test]
+fn from_row_option_lifting() {
+    let connection = Connection::open_in_memory().unwrap();
+
+    let assignment = connection
+        .query_row(
+            "SELECT 1 AS author, null AS reviewer, 2 AS priority, 'a.txt' AS attachment, null AS contact, 'b.txt' AS fallback",
+            params![],
+            Assignment::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(assignment.author, Some(DbId(1)));
+    assert_eq!(assignment.reviewer, None);
+    assert_eq!(assignment.priority, Some(Positive(2)));
+    assert_eq!(assignment.attachment, Some(PathBuf::from("a.txt")));
+    assert_eq!(assignment.contact, None);
+    assert_eq!(assignment.fallback, Some(PathBuf::from("b.txt")));
+
+    let assignment = connection
+        .query_row(
+            "SELECT null AS author, 3 AS reviewer, null AS priority, null AS attachment, 'john@example.com' AS contact, null AS fallback",
+            params![],
+            Assignment::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(assignment.author, None);
+    assert_eq!(assignment.reviewer, Some(DbId(3)));
+    assert_eq!(assignment.priority, None);
+    assert_eq!(assignment.attachment, None);
+    assert_eq!(
+        assignment.contact,
+        Some(Email("john@example.com".to_string()))
+    );
+    assert_eq!(assignment.fallback, Some(PathBuf::from("default.txt")));
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Level {
+    Low,
+    High,
+}
+
+impl std::str::FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "low" => Ok(Self::Low),
+            "high" => Ok(Self::High),
+            _ => Err("unknown level".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct Host {
+    #[from_row(parse)]
+    address: IpAddr,
+    #[from_row(parse)]
+    gateway: Option<IpAddr>,
+    #[from_row(parse, rename = "risk")]
+    level: Level,
+}
+
+#[test]
+fn from_row_parse() {
+    let connection = Connection::open_in_memory().unwrap();
+
+    let host = connection
+        .query_row(
+            "SELECT '127.0.0.1' AS address, null AS gateway, 'high' AS risk",
+            params![],
+            Host::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(host.address, IpAddr::from([127, 0, 0, 1]));
+    assert_eq!(host.gateway, None);
+    assert_eq!(host.level, Level::High);
+
+    let err = connection
+        .query_row(
+            "SELECT '127.0.0.1' AS address, '::1' AS gateway, 'extreme' AS risk",
+            params![],
+            Host::try_from_row,
+        )
+        .unwrap_err();
+
+    let rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, err) = err else {
+        panic!("unexpected error: {err:?}");
+    };
+
+    assert_eq!(
+        err.to_string(),
+        "invalid value for column `risk`: can't parse `extreme`: unknown level"
+    );
+}
+
+fn timestamp_from_value(value: ValueRef<'_>, context: &ColumnContext) -> FromSqlResult<i64> {
+    match value {
+        ValueRef::Integer(seconds) => Ok(seconds),
+        ValueRef::Text(_) => value
+            .as_str()?
+            .parse::<i64>()
+            .map(|millis| millis / 1000)
+            .map_err(|err| FromSqlError::Other(err.into())),
+        _ => Err(FromSqlError::Other(
+            format!("unexpected value in column `{}`", context.name()).into(),
+        )),
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct Visit {
+    #[from_row(from_value = "timestamp_from_value")]
+    seen: i64,
+    #[from_row(from_value = "timestamp_from_value")]
+    left: Option<i64>,
+}
+
+#[test]
+fn from_row_from_value() {
+    let connection = Connection::open_in_memory().unwrap();
+
+    connection
+        .execute_batch(
+            "
+            CREATE TABLE visit (seen, left);
+            INSERT INTO visit VALUES (60, '120000'), ('180000', null);
+            ",
+        )
+        .unwrap();
+
+    let visits = connection
+        .prepare("SELECT seen, left FROM visit ORDER BY rowid")
+        .unwrap()
+        .query_map(params![], Visit::try_from_row)
+        .unwrap()
+        .map(|visit| visit.map(|visit| (visit.seen, visit.left)))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(visits, [(60, Some(120)), (180, None)]);
+
+    let err = connection
+        .query_row(
+            "SELECT 1.5 AS seen, null AS left",
+            params![],
+            Visit::try_from_row,
+        )
+        .unwrap_err();
+
+    let rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Real, err) = err else {
+        panic!("unexpected error: {err:?}");
+    };
+
+    assert_eq!(err.to_string(), "unexpected value in column `seen`");
+}
+
+#[cfg(feature = "column_decltype")]
+#[test]
+fn from_row_from_value_decl_type() {
+    fn decl_type(_: ValueRef<'_>, context: &ColumnContext) -> FromSqlResult<String> {
+        Ok(context.decl_type().unwrap_or_else(|| "none".to_string()))
+    }
+
+    #[derive(FromRow)]
+    struct DeclType {
+        #[from_row(from_value = "decl_type")]
+        created: String,
+        #[from_row(from_value = "decl_type")]
+        computed: String,
+    }
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    connection
+        .execute_batch("CREATE TABLE event (created DATETIME); INSERT INTO event VALUES (1);")
+        .unwrap();
+
+    let decl_type = connection
+        .query_row(
+            "SELECT created, 1 AS computed FROM event",
+            params![],
+            DeclType::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(decl_type.created, "DATETIME");
+    assert_eq!(decl_type.computed, "none");
+}
+
+#[cfg(all(feature = "chrono", feature = "time", feature = "jiff"))]
+#[test]
+fn from_row_datetime() {
+    #[derive(FromRow)]
+    struct Event {
+        #[from_row(datetime)]
+        chrono: chrono::DateTime<chrono::Utc>,
+        #[from_row(datetime)]
+        naive: chrono::NaiveDateTime,
+        #[from_row(datetime)]
+        time: time::OffsetDateTime,
+        #[from_row(datetime)]
+        jiff: jiff::Timestamp,
+        #[from_row(datetime(unit = "millis"))]
+        millis: Option<jiff::civil::DateTime>,
+    }
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    connection
+        .execute_batch(
+            "CREATE TABLE event (at, millis);
+            INSERT INTO event VALUES ('2024-01-02 03:04:05', 1704164645000);
+            INSERT INTO event VALUES ('2024-01-02T05:04:05.000+02:00', null);
+            INSERT INTO event VALUES (julianday('2024-01-02 03:04:05'), 1704164645000);
+            INSERT INTO event VALUES (1704164645, null);",
+        )
+        .unwrap();
+
+    let events = connection
+        .prepare("SELECT at AS chrono, at AS naive, at AS time, at AS jiff, millis FROM event")
+        .unwrap()
+        .query_map(params![], Event::try_from_row)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    for event in &events {
+        assert_eq!(event.chrono.timestamp(), 1704164645);
+        assert_eq!(event.naive.to_string(), "2024-01-02 03:04:05");
+        assert_eq!(event.time.unix_timestamp(), 1704164645);
+        assert_eq!(event.jiff.as_second(), 1704164645);
+    }
+
+    let millis = events
+        .iter()
+        .map(|event| event.millis.map(|millis| millis.to_string()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        millis,
+        [
+            Some("2024-01-02T03:04:05".to_string()),
+            None,
+            Some("2024-01-02T03:04:05".to_string()),
+            None,
+        ]
+    );
+
+    let err = connection
+        .query_row("SELECT 'yesterday' AS chrono", params![], |row| {
+            #[derive(FromRow)]
+            struct Invalid {
+                #[from_row(datetime)]
+                chrono: chrono::DateTime<chrono::Utc>,
+            }
+
+            Invalid::try_from_row(row).map(|invalid| invalid.chrono)
+        })
+        .unwrap_err();
+
+    assert!(err.to_string().contains("invalid datetime `yesterday`"));
+}
+
+#[test]
+fn from_row_coerce() {
+    #[derive(FromRow)]
+    #[from_row(coerce)]
+    struct Loose {
+        count: i64,
+        ratio: f64,
+        active: bool,
+        label: String,
+        missing: Option<i64>,
+    }
+
+    #[derive(Debug, FromRow)]
+    struct Strict {
+        #[from_row(coerce)]
+        count: u8,
+        active: bool,
+    }
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    let loose = connection
+        .query_row(
+            "SELECT ' 42 ' AS count, 2 AS ratio, 'Yes' AS active, 1.5 AS label, null AS missing",
+            params![],
+            Loose::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(loose.count, 42);
+    assert_eq!(loose.ratio, 2.0);
+    assert!(loose.active);
+    assert_eq!(loose.label, "1.5");
+    assert_eq!(loose.missing, None);
+
+    let loose = connection
+        .query_row(
+            "SELECT 7.0 AS count, '0.25' AS ratio, 'f' AS active, 'text' AS label, '3' AS missing",
+            params![],
+            Loose::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(loose.count, 7);
+    assert_eq!(loose.ratio, 0.25);
+    assert!(!loose.active);
+    assert_eq!(loose.missing, Some(3));
+
+    let strict = connection
+        .query_row(
+            "SELECT '5' AS count, 1 AS active",
+            params![],
+            Strict::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(strict.count, 5);
+    assert!(strict.active);
+
+    let err = connection
+        .query_row(
+            "SELECT 'many' AS count, 1 AS active",
+            params![],
+            Strict::try_from_row,
+        )
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "Conversion error from type Text at index: 0, invalid value for column `count`: can't coerce TEXT `many` into `u8`"
+    );
+
+    // Fields without `coerce` keep the strict behaviour of `FromSql`.
+    let err = connection
+        .query_row(
+            "SELECT '1' AS count, 'yes' AS active",
+            params![],
+            Strict::try_from_row,
+        )
+        .unwrap_err();
+
+    assert!(matches!(err, rusqlite::Error::InvalidColumnType(1, _, _)));
+
+    // Coerced values are still range checked.
+    let err = connection
+        .query_row(
+            "SELECT '300' AS count, 1 AS active",
+            params![],
+            Strict::try_from_row,
+        )
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        rusqlite::Error::FromSqlConversionFailure(0, _, _)
+    ));
+}
+
+#[cfg(all(
+    feature = "cbor",
+    feature = "msgpack",
+    feature = "bincode",
+    feature = "postcard",
+    feature = "zstd",
+    feature = "deflate"
+))]
+#[test]
+fn from_row_codec() {
+    use std::io::Write;
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Payload {
+        id: u32,
+        tags: Vec<String>,
+    }
+
+    #[derive(FromRow)]
+    struct Cache {
+        #[from_row(codec = "cbor")]
+        cbor: Payload,
+        #[from_row(codec = "msgpack", compress = "zstd")]
+        msgpack: Payload,
+        #[from_row(codec = "bincode", compress = "deflate")]
+        bincode: Option<Payload>,
+        #[from_row(codec = "postcard")]
+        postcard: Option<Payload>,
+    }
+
+    let payload = Payload {
+        id: 7,
+        tags: vec!["a".to_string(), "b".to_string()],
+    };
+
+    let mut cbor = Vec::new();
+    ciborium::into_writer(&payload, &mut cbor).unwrap();
+
+    let msgpack = zstd::encode_all(&*rmp_serde::to_vec(&payload).unwrap(), 0).unwrap();
+
+    let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), Default::default());
+    encoder
+        .write_all(&bincode::serialize(&payload).unwrap())
+        .unwrap();
+    let bincode = encoder.finish().unwrap();
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    let cache = connection
+        .query_row(
+            "SELECT ? AS cbor, ? AS msgpack, ? AS bincode, null AS postcard",
+            params![cbor, msgpack, bincode],
+            Cache::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(cache.cbor, payload);
+    assert_eq!(cache.msgpack, payload);
+    assert_eq!(cache.bincode, Some(payload));
+    assert_eq!(cache.postcard, None);
+
+    let err = connection
+        .query_row(
+            "SELECT ? AS cbor, ? AS msgpack, null AS bincode, null AS postcard",
+            params![cbor, b"not zstd".to_vec()],
+            |row| Cache::try_from_row(row).map(|cache| cache.cbor),
+        )
+        .unwrap_err();
+
+    let rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Blob, err) = err else {
+        panic!("unexpected error: {err:?}");
+    };
+
+    assert!(err
+        .to_string()
+        .starts_with("invalid value for column `msgpack`: can't decode zstd: "));
+}
+
+#[test]
+fn from_row_columns() {
+    #[derive(FromRow)]
+    struct Revenue {
+        year: i64,
+        #[from_row(columns = "q{}_revenue", range = "1..=4")]
+        quarters: [i64; 4],
+        #[from_row(columns = "score_{}", range = "0..3")]
+        scores: Vec<Option<i64>>,
+    }
+
+    #[derive(FromRow)]
+    struct Report {
+        #[from_row(flatten, prefix)]
+        current: Revenue,
+        #[from_row(flatten, prefix)]
+        previous: Option<Revenue>,
+    }
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    let report = connection
+        .query_row(
+            "SELECT
+                2024 AS current_year, 10 AS current_q1_revenue, 20 AS current_q2_revenue,
+                30 AS current_q3_revenue, 40 AS current_q4_revenue, 1 AS current_score_0,
+                null AS current_score_1, 3 AS current_score_2,
+                null AS previous_year, null AS previous_q1_revenue, null AS previous_q2_revenue,
+                null AS previous_q3_revenue, null AS previous_q4_revenue,
+                null AS previous_score_0, null AS previous_score_1, null AS previous_score_2",
+            params![],
+            Report::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(report.current.year, 2024);
+    assert_eq!(report.current.quarters, [10, 20, 30, 40]);
+    assert_eq!(report.current.scores, [Some(1), None, Some(3)]);
+    assert!(report.previous.is_none());
+
+    let previous = connection
+        .query_row(
+            "SELECT
+                null AS previous_year, 1 AS previous_q1_revenue, 2 AS previous_q2_revenue,
+                3 AS previous_q3_revenue, 4 AS previous_q4_revenue,
+                null AS previous_score_0, null AS previous_score_1, null AS previous_score_2",
+            params![],
+            |row| {
+                <Option<Revenue> as FromRow>::try_from_row_prefixed(row, Some("previous_"))
+                    .map(|previous| previous.is_some())
+            },
+        )
+        .unwrap_err();
+
+    // A column of the group being non null means the struct isn't all null.
+    assert!(matches!(previous, rusqlite::Error::InvalidColumnType(..)));
+}
+
+#[test]
+fn from_row_split() {
+    #[derive(FromRow)]
+    struct Post {
+        #[from_row(split = ",")]
+        tags: Vec<String>,
+        #[from_row(split = ",", trim)]
+        scores: Vec<u8>,
+        #[from_row(split = " | ")]
+        hosts: Option<std::collections::BTreeSet<IpAddr>>,
+    }
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    connection
+        .execute_batch(
+            "CREATE TABLE tag (post, name, score);
+            INSERT INTO tag VALUES (1, 'rust', 3), (1, 'sqlite', 5), (2, null, null);",
+        )
+        .unwrap();
+
+    let posts = connection
+        .prepare(
+            "SELECT group_concat(name, ',') AS tags, group_concat(score, ' , ') AS scores,
+                '::1 | 127.0.0.1' AS hosts
+            FROM tag GROUP BY post ORDER BY post",
+        )
+        .unwrap()
+        .query_map(params![], Post::try_from_row)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(posts[0].tags, ["rust", "sqlite"]);
+    assert_eq!(posts[0].scores, [3, 5]);
+    assert_eq!(posts[0].hosts.as_ref().unwrap().len(), 2);
+    assert!(posts[1].tags.is_empty());
+    assert!(posts[1].scores.is_empty());
+
+    let post = connection
+        .query_row(
+            "SELECT '' AS tags, null AS scores, null AS hosts",
+            params![],
+            Post::try_from_row,
+        )
+        .unwrap();
+
+    assert!(post.tags.is_empty());
+    assert_eq!(post.hosts, None);
+
+    let err = connection
+        .query_row(
+            "SELECT 'a' AS tags, '1,300' AS scores, null AS hosts",
+            params![],
+            |row| Post::try_from_row(row).map(|post| post.scores),
+        )
+        .unwrap_err();
+
+    let rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, err) = err else {
+        panic!("unexpected error: {err:?}");
+    };
+
+    assert_eq!(
+        err.to_string(),
+        "invalid value for column `scores`: can't parse `300`: number too large to fit in target type"
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn from_row_json_rows() {
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Tag {
+        id: i64,
+        #[serde(rename = "label")]
+        name: String,
+        color: Option<Color>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Color {
+        hex: String,
+    }
+
+    #[derive(FromRow)]
+    struct Article {
+        id: i64,
+        #[from_row(json_rows)]
+        tags: Vec<Tag>,
+        #[from_row(json_rows)]
+        related: Option<Vec<Tag>>,
+    }
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    connection
+        .execute_batch(
+            "CREATE TABLE article (id INTEGER PRIMARY KEY);
+            CREATE TABLE tag (article_id, id, label, color_hex);
+            INSERT INTO article VALUES (1), (2);
+            INSERT INTO tag VALUES (1, 10, 'rust', '#dea584'), (1, 11, 'sql', null);",
+        )
+        .unwrap();
+
+    let articles = connection
+        .prepare(
+            "SELECT article.id, (
+                SELECT json_group_array(json_object(
+                    'id', tag.id,
+                    'label', tag.label,
+                    'color', json(iif(tag.color_hex IS NULL, 'null', json_object('hex', tag.color_hex)))
+                ))
+                FROM tag WHERE tag.article_id = article.id
+            ) AS tags, null AS related
+            FROM article ORDER BY article.id",
+        )
+        .unwrap()
+        .query_map(params![], Article::try_from_row)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(articles[0].id, 1);
+    assert_eq!(
+        articles[0].tags,
+        [
+            Tag {
+                id: 10,
+                name: "rust".to_string(),
+                color: Some(Color {
+                    hex: "#dea584".to_string()
+                }),
+            },
+            Tag {
+                id: 11,
+                name: "sql".to_string(),
+                color: None,
+            },
+        ]
+    );
+    assert_eq!(articles[0].related, None);
+    assert!(articles[1].tags.is_empty());
+
+    #[derive(Debug, Deserialize)]
+    struct Quoted {
+        #[serde(rename = "a\"b")]
+        quoted: i64,
+        #[serde(rename = "c.d")]
+        dotted: i64,
+    }
+
+    let quoted: Vec<Quoted> = connection
+        .query_row(
+            r#"SELECT json_array(json_object('a"b', 1, 'c.d', 2)) AS rows"#,
+            params![],
+            |row| rusqlite_from_row::json::get_json_rows(row, "rows"),
+        )
+        .unwrap();
+
+    assert_eq!((quoted[0].quoted, quoted[0].dotted), (1, 2));
+
+    let err = connection
+        .query_row(
+            "SELECT 1 AS id, '[{\"id\": 1}]' AS tags, '[]' AS related",
+            params![],
+            |row| Article::try_from_row(row).map(|article| article.id),
+        )
+        .unwrap_err();
+
+    let rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, err) = err else {
+        panic!("unexpected error: {err:?}");
+    };
+
+    assert!(err
+        .to_string()
+        .starts_with("invalid value for column `tags`: missing field `label`"));
+
+    let err = connection
+        .query_row(
+            "SELECT 1 AS id, '[1, 2]' AS tags, null AS related",
+            params![],
+            |row| Article::try_from_row(row).map(|article| article.id),
+        )
+        .unwrap_err();
+
+    assert!(err.to_string().contains("invalid type: integer `1`"));
+}
+
+#[test]
+fn from_row_tree() {
+    use rusqlite_from_row::tree::{build_tree, TreeError};
+
+    #[derive(Debug, FromRow)]
+    struct Category {
+        #[from_row(key)]
+        id: i64,
+        #[from_row(parent_key)]
+        parent_id: Option<i64>,
+        name: String,
+        #[from_row(children)]
+        children: Vec<Category>,
+    }
+
+    fn names(categories: &[Category]) -> Vec<String> {
+        categories
+            .iter()
+            .map(|category| {
+                if category.children.is_empty() {
+                    category.name.clone()
+                } else {
+                    format!("{}({})", category.name, names(&category.children).join(" "))
+                }
+            })
+            .collect()
+    }
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    connection
+        .execute_batch(
+            "CREATE TABLE category (id INTEGER PRIMARY KEY, parent_id, name);
+            INSERT INTO category VALUES
+                (1, null, 'root'), (2, 1, 'a'), (3, 1, 'b'), (4, 3, 'c'), (5, null, 'other'),
+                (10, 11, 'x'), (11, 10, 'y');",
+        )
+        .unwrap();
+
+    let mut statement = connection
+        .prepare(
+            "WITH RECURSIVE tree(id, parent_id, name, depth) AS (
+                SELECT id, parent_id, name, 0 FROM category WHERE parent_id IS NULL
+                UNION ALL
+                SELECT c.id, c.parent_id, c.name, tree.depth + 1
+                FROM category c JOIN tree ON c.parent_id = tree.id
+            )
+            SELECT id, parent_id, name FROM tree ORDER BY depth DESC, id",
+        )
+        .unwrap();
+
+    let roots = build_tree(
+        statement
+            .query_map(params![], Category::try_from_row)
+            .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(names(&roots), ["root(a b(c))", "other"]);
+
+    let mut statement = connection
+        .prepare("SELECT id, parent_id, name FROM category WHERE id <> 1 ORDER BY id")
+        .unwrap();
+    let err = build_tree(
+        statement
+            .query_map(params![], Category::try_from_row)
+            .unwrap(),
+    )
+    .unwrap_err();
+
+    assert!(matches!(err, TreeError::Orphan { key: 2, parent: 1 }));
+    assert_eq!(err.to_string(), "node `2` refers to missing parent `1`");
+
+    let mut statement = connection
+        .prepare("SELECT id, parent_id, name FROM category ORDER BY id")
+        .unwrap();
+    let err = build_tree(
+        statement
+            .query_map(params![], Category::try_from_row)
+            .unwrap(),
+    )
+    .unwrap_err();
+
+    assert!(matches!(err, TreeError::Cycle(10)));
+
+    // A node that only descends from the cycle isn't reported as part of it, even when it comes
+    // first.
+    let mut statement = connection
+        .prepare(
+            "SELECT 9 AS id, 11 AS parent_id, 'z' AS name
+            UNION ALL SELECT id, parent_id, name FROM category WHERE id >= 10",
+        )
+        .unwrap();
+    let err = build_tree(
+        statement
+            .query_map(params![], Category::try_from_row)
+            .unwrap(),
+    )
+    .unwrap_err();
+
+    assert!(matches!(err, TreeError::Cycle(11)));
+
+    let mut statement = connection
+        .prepare("SELECT id, parent_id, name FROM category UNION ALL SELECT 1, null, 'again'")
+        .unwrap();
+    let err = build_tree(
+        statement
+            .query_map(params![], Category::try_from_row)
+            .unwrap(),
+    )
+    .unwrap_err();
+
+    assert!(matches!(err, TreeError::DuplicateKey(1)));
+}
+
+#[test]
+fn from_row_max_depth() {
+    #[derive(FromRow)]
+    struct Category {
+        name: String,
+        #[from_row(flatten, prefix, max_depth = 2)]
+        parent: Option<Box<Category>>,
+    }
+
+    #[derive(FromRow)]
+    struct Shared {
+        #[from_row(flatten)]
+        category: std::rc::Rc<Category>,
+        #[from_row(flatten, prefix = "other_")]
+        other: Option<std::sync::Arc<Category>>,
+    }
+
+    #[derive(FromRow)]
+    struct Outer {
+        #[from_row(flatten, prefix)]
+        parent: Category,
+        #[from_row(flatten, prefix)]
+        grandparent: Category,
+    }
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    let category = connection
+        .query_row(
+            "SELECT 'c' AS name, 'b' AS parent_name, 'a' AS parent_parent_name",
+            params![],
+            Category::try_from_row,
+        )
+        .unwrap();
+
+    let parent = category.parent.unwrap();
+    let grandparent = parent.parent.unwrap();
+
+    assert_eq!(category.name, "c");
+    assert_eq!(parent.name, "b");
+    assert_eq!(grandparent.name, "a");
+    assert!(grandparent.parent.is_none());
+
+    let category = connection
+        .query_row(
+            "SELECT 'b' AS name, 'a' AS parent_name, null AS parent_parent_name",
+            params![],
+            Category::try_from_row,
+        )
+        .unwrap();
+
+    assert!(category.parent.unwrap().parent.is_none());
+
+    let shared = connection
+        .query_row(
+            "SELECT 'b' AS name, 'a' AS parent_name, null AS parent_parent_name,
+                null AS other_name, null AS other_parent_name, null AS other_parent_parent_name",
+            params![],
+            Shared::try_from_row,
+        )
+        .unwrap();
+
+    assert_eq!(shared.category.name, "b");
+    assert!(shared.other.is_none());
+
+    // The depth is counted from the struct with `max_depth`, not from the outer prefix, and
+    // `grandparent_` isn't mistaken for a nested `parent_`.
+    let outer = connection
+        .query_row(
+            "SELECT 'c' AS parent_name, 'b' AS parent_parent_name,
+                'a' AS parent_parent_parent_name, 'z' AS grandparent_name,
+                'y' AS grandparent_parent_name, 'x' AS grandparent_parent_parent_name",
+            params![],
+            Outer::try_from_row,
+        )
+        .unwrap();
+
+    let parent = outer.parent.parent.unwrap();
+
+    assert_eq!(outer.parent.name, "c");
+    assert_eq!(parent.name, "b");
+    assert_eq!(parent.parent.as_ref().unwrap().name, "a");
+    assert!(parent.parent.unwrap().parent.is_none());
+
+    let parent = outer.grandparent.parent.unwrap();
+
+    assert_eq!(outer.grandparent.name, "z");
+    assert_eq!(parent.name, "y");
+    assert_eq!(parent.parent.unwrap().name, "x");
+}
+
+#[test]
+fn from_row_relation() {
+    use rusqlite_from_row::relation::{attach_related, load_related};
+
+    #[derive(FromRow)]
+    struct Todo {
+        id: i64,
+        #[from_row(skip)]
+        comments: Vec<Comment>,
+    }
+
+    #[derive(FromRow)]
+    struct Comment {
+        todo_id: i64,
+        text: String,
+    }
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    connection
+        .execute_batch(
+            "CREATE TABLE todo (id INTEGER PRIMARY KEY);
+            CREATE TABLE comment (id INTEGER PRIMARY KEY, todo_id, text);",
+        )
+        .unwrap();
+
+    for id in 0..1200 {
+        connection
+            .execute("INSERT INTO todo VALUES (?)", params![id])
+            .unwrap();
+    }
+
+    connection
+        .execute_batch(
+            "INSERT INTO comment (todo_id, text) VALUES
+                (1, 'first'), (2, 'other'), (1, 'second'), (1100, 'late');",
+        )
+        .unwrap();
+
+    let mut todos = connection
+        .prepare("SELECT id FROM todo ORDER BY id")
+        .unwrap()
+        .query_map(params![], Todo::try_from_row)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let sql = "SELECT todo_id, text FROM comment WHERE todo_id IN ({keys}) ORDER BY id";
+
+    let related = load_related(
+        &connection,
+        sql,
+        &todos[..10],
+        |todo| todo.id,
+        |comment: &Comment| comment.todo_id,
+    )
+    .unwrap();
+
+    assert_eq!(related.len(), 2);
+    assert_eq!(related[&1].len(), 2);
+
+    // More keys than fit in a single query.
+    attach_related(
+        &connection,
+        sql,
+        &mut todos,
+        |todo| todo.id,
+        |comment: &Comment| comment.todo_id,
+        |todo, comments| todo.comments = comments,
+    )
+    .unwrap();
+
+    fn texts(todo: &Todo) -> Vec<&str> {
+        todo.comments
+            .iter()
+            .map(|comment| comment.text.as_str())
+            .collect()
+    }
+
+    assert_eq!(texts(&todos[1]), ["first", "second"]);
+    assert_eq!(texts(&todos[2]), ["other"]);
+    assert_eq!(texts(&todos[1100]), ["late"]);
+    assert!(todos[0].comments.is_empty());
+
+    // The keys are bound once, however often they are used.
+    let related = load_related(
+        &connection,
+        "SELECT todo_id, text FROM comment
+            WHERE todo_id IN ({keys}) AND todo_id IN ({keys}) ORDER BY id",
+        &todos[..3],
+        |todo| todo.id,
+        |comment: &Comment| comment.todo_id,
+    )
+    .unwrap();
+
+    assert_eq!(related[&1].len(), 2);
+    assert_eq!(related[&2].len(), 1);
+}
+
+#[test]
+fn from_row_collect() {
+    use std::collections::{BTreeMap, HashMap};
+
+    use rusqlite_from_row::collect::{DuplicateKey, StatementExt};
+
+    #[derive(Debug, FromRow)]
+    struct Todo {
+        #[from_row(key)]
+        id: i64,
+        text: String,
+    }
+
+    #[derive(Debug, FromRow)]
+    struct Tag {
+        #[from_row(index = 0)]
+        name: String,
+    }
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    connection
+        .execute_batch(
+            "CREATE TABLE todo (id, text, owner);
+            INSERT INTO todo VALUES (1, 'a', 'x'), (2, 'b', 'y'), (1, 'c', 'x');",
+        )
+        .unwrap();
+
+    let mut statement = connection
+        .prepare("SELECT id, text FROM todo ORDER BY rowid")
+        .unwrap();
+
+    let first: HashMap<i64, Todo> = statement
+        .query_map_keyed(params![], DuplicateKey::FirstWins)
+        .unwrap();
+    let last: BTreeMap<i64, Todo> = statement
+        .query_map_keyed(params![], DuplicateKey::LastWins)
+        .unwrap();
+
+    assert_eq!(first[&1].text, "a");
+    assert_eq!(last[&1].text, "c");
+    assert_eq!(last[&2].text, "b");
+
+    let err = statement
+        .query_map_keyed::<Todo, HashMap<_, _>, _>(params![], DuplicateKey::Error)
+        .unwrap_err();
+
+    let rusqlite::Error::ToSqlConversionFailure(err) = err else {
+        panic!("unexpected error: {err:?}");
+    };
+
+    assert_eq!(err.to_string(), "more than one row has key `1`");
+
+    let grouped: BTreeMap<i64, Vec<Todo>> = statement.query_grouped(params![]).unwrap();
+
+    assert_eq!(grouped[&1].len(), 2);
+    assert_eq!(grouped[&2].len(), 1);
+
+    let mut statement = connection
+        .prepare("SELECT owner, text FROM todo ORDER BY rowid")
+        .unwrap();
+
+    let by_owner: HashMap<String, Vec<Tag>> = statement.query_grouped_by_column(params![]).unwrap();
+
+    let names = by_owner["x"]
+        .iter()
+        .map(|tag| tag.name.as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(names, ["a", "c"]);
+
+    let err = statement
+        .query_map_keyed_by_column::<String, Tag, HashMap<_, _>, _>(params![], DuplicateKey::Error)
+        .unwrap_err();
+
+    assert!(matches!(err, rusqlite::Error::ToSqlConversionFailure(_)));
+    assert_eq!(err.to_string(), "more than one row has key `\"x\"`");
+}
+
+#[test]
+fn from_row_table() {
+    use rusqlite_from_row::Table;
+
+    #[derive(Debug, PartialEq, FromRow, Table)]
+    #[from_row(rename_all = "camelCase")]
+    #[table(name = "todo")]
+    struct Todo {
+        #[table(primary_key)]
+        id: Option<i64>,
+        #[from_row(rename = "body")]
+        text: String,
+        done_at: Option<i64>,
+        #[from_row(skip)]
+        selected: bool,
+    }
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    connection
+        .execute_batch(r#"CREATE TABLE todo (id INTEGER PRIMARY KEY, body TEXT, "doneAt" INTEGER)"#)
+        .unwrap();
+
+    assert_eq!(Todo::COLUMNS, ["id", "body", "doneAt"]);
+
+    let mut todo = Todo {
+        id: None,
+        text: "write tests".to_string(),
+        done_at: None,
+        selected: true,
+    };
+
+    assert_eq!(todo.insert(&connection).unwrap(), 1);
+
+    todo.id = Some(connection.last_insert_rowid());
+    todo.done_at = Some(10);
+
+    assert_eq!(todo.update(&connection).unwrap(), 1);
+
+    let loaded = Todo::get_by_id(&connection, 1).unwrap().unwrap();
+
+    assert_eq!(loaded.text, "write tests");
+    assert_eq!(loaded.done_at, Some(10));
+    assert!(!loaded.selected);
+    assert!(Todo::get_by_id(&connection, 2).unwrap().is_none());
+
+    let other = Todo {
+        id: Some(2),
+        text: "review".to_string(),
+        done_at: None,
+        selected: false,
+    };
+
+    other.upsert(&connection).unwrap();
+    todo.text = "write more tests".to_string();
+    todo.upsert(&connection).unwrap();
+
+    let all = Todo::find_all(&connection).unwrap();
+
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[0].text, "write more tests");
+    assert_eq!(all[1], other);
+
+    assert_eq!(todo.delete(&connection).unwrap(), 1);
+    assert_eq!(Todo::find_all(&connection).unwrap(), [other]);
+
+    #[derive(FromRow, Table)]
+    #[table(name = "tag")]
+    struct Tag {
+        #[table(primary_key)]
+        name: String,
+    }
+
+    connection
+        .execute_batch("CREATE TABLE tag (name TEXT PRIMARY KEY)")
+        .unwrap();
+
+    let tag = Tag {
+        name: "home".to_string(),
+    };
+
+    tag.insert(&connection).unwrap();
+
+    // There is nothing to update besides the primary key.
+    assert_eq!(tag.update(&connection).unwrap(), 0);
+}
+
+#[test]
+fn from_row_returning() {
+    use rusqlite_from_row::{returning::ConnectionExt, Table};
+
+    #[derive(Debug, FromRow, Table)]
+    #[table(name = "todo")]
+    struct Todo {
+        #[table(primary_key)]
+        id: Option<i64>,
+        text: String,
+        done: bool,
+    }
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    connection
+        .execute_batch(
+            "CREATE TABLE todo (id INTEGER PRIMARY KEY, text TEXT NOT NULL, done INTEGER NOT NULL DEFAULT 0)",
+        )
+        .unwrap();
+
+    let todo: Todo = connection
+        .insert_returning(
+            "INSERT INTO todo (text) VALUES (?1) RETURNING *",
+            ["laundry"],
+        )
+        .unwrap();
+
+    assert_eq!(todo.id, Some(1));
+    assert_eq!(todo.text, "laundry");
+    assert!(!todo.done);
+
+    let todo = Todo {
+        id: None,
+        text: "dishes".to_string(),
+        done: true,
+    }
+    .insert_returning(&connection)
+    .unwrap();
+
+    assert_eq!(todo.id, Some(2));
+
+    let done: Vec<Todo> = connection
+        .execute_returning_all("UPDATE todo SET done = 1 RETURNING *", params![])
+        .unwrap();
+
+    assert_eq!(done.len(), 2);
+    assert!(done.iter().all(|todo| todo.done));
+
+    let err = connection
+        .insert_returning::<Todo, _>("DELETE FROM todo WHERE id = 3 RETURNING *", params![])
+        .unwrap_err();
+
+    assert!(matches!(err, rusqlite::Error::QueryReturnedNoRows));
+}
+
+#[test]
+fn from_row_patch() {
+    use rusqlite_from_row::Table;
+
+    #[derive(Debug, FromRow, Table)]
+    #[table(name = "todo", patch = "TodoPatch")]
+    struct Todo {
+        #[table(primary_key)]
+        id: i64,
+        #[from_row(rename = "body")]
+        text: String,
+        due: Option<i64>,
+        #[from_row(skip)]
+        selected: bool,
+    }
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    connection
+        .execute_batch(
+            "CREATE TABLE todo (id INTEGER PRIMARY KEY, body TEXT, due INTEGER);
+            INSERT INTO todo VALUES (1, 'laundry', 10);",
+        )
+        .unwrap();
+
+    assert!(TodoPatch::default().sql().is_none());
+    assert_eq!(TodoPatch::default().execute(&connection, 1).unwrap(), 0);
+
+    let patch = TodoPatch {
+        due: Some(None),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        patch.sql().unwrap(),
+        r#"UPDATE "todo" SET "due" = :due WHERE "id" = :id"#
+    );
+    assert_eq!(patch.params().unwrap().len(), 1);
+    assert_eq!(patch.execute(&connection, 1).unwrap(), 1);
+
+    let mut todo = Todo::get_by_id(&connection, 1).unwrap().unwrap();
+
+    assert_eq!(todo.due, None);
+
+    let patch = TodoPatch {
+        text: Some("dishes".to_string()),
+        due: Some(Some(20)),
+    };
+
+    assert_eq!(patch.execute(&connection, 1).unwrap(), 1);
+
+    patch.apply(&mut todo);
+
+    let loaded = Todo::get_by_id(&connection, 1).unwrap().unwrap();
+
+    assert_eq!(
+        (todo.id, todo.text, todo.due),
+        (1, "dishes".to_string(), Some(20))
+    );
+    assert_eq!((loaded.text, loaded.due), ("dishes".to_string(), Some(20)));
+    assert!(!todo.selected);
+}
+
+#[test]
+fn from_row_tracked() {
+    use rusqlite_from_row::{table::Tracked, Table};
+
+    #[derive(Debug, FromRow, Table)]
+    #[table(name = "todo")]
+    struct Todo {
+        #[table(primary_key)]
+        id: i64,
+        #[from_row(rename = "body")]
+        text: String,
+        due: Option<i64>,
+    }
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    connection
+        .execute_batch(
+            "CREATE TABLE todo (id INTEGER PRIMARY KEY, body TEXT, due INTEGER);
+            CREATE TABLE log (message TEXT);
+            CREATE TRIGGER due_changed AFTER UPDATE OF due ON todo BEGIN
+                INSERT INTO log VALUES ('due');
+            END;
+            INSERT INTO todo VALUES (1, 'laundry', 10);",
+        )
+        .unwrap();
+
+    let mut todo = connection
+        .query_row(
+            "SELECT * FROM todo WHERE id = 1",
+            params![],
+            Tracked::<Todo>::try_from_row,
+        )
+        .unwrap();
+
+    assert!(todo.changed_columns().unwrap().is_empty());
+    assert_eq!(todo.update(&connection).unwrap(), 0);
+
+    todo.text = "dishes".to_string();
+    todo.due = Some(10);
+
+    assert_eq!(todo.changed_columns().unwrap(), ["body"]);
+    assert_eq!(todo.update(&connection).unwrap(), 1);
+    assert!(todo.changed_columns().unwrap().is_empty());
+
+    let logged: i64 = connection
+        .query_row("SELECT count(*) FROM log", params![], |row| row.get(0))
+        .unwrap();
+
+    assert_eq!(logged, 0);
+
+    todo.id = 2;
+    todo.due = None;
+
+    assert_eq!(todo.update(&connection).unwrap(), 1);
+
+    let todo = Todo::get_by_id(&connection, 2).unwrap().unwrap();
+
+    assert_eq!((todo.text.as_str(), todo.due), ("dishes", None));
+}
+
+#[test]
+fn from_row_create_table() {
+    use rusqlite_from_row::Table;
+
+    #[derive(Debug, FromRow, Table)]
+    #[table(name = "user")]
+    struct User {
+        #[table(primary_key)]
+        id: Option<i64>,
+        #[table(unique)]
+        email: String,
+    }
+
+    #[derive(Debug, FromRow, Table)]
+    #[table(name = "todo", strict)]
+    struct Todo {
+        #[table(primary_key)]
+        id: i64,
+        #[from_row(rename = "body")]
+        text: String,
+        #[table(default = "0")]
+        done: bool,
+        score: Option<f64>,
+        attachment: Option<Vec<u8>>,
+        #[table(references = "user(id)")]
+        owner: Option<i64>,
+        #[table(sql_type = "INT")]
+        priority: i32,
+        #[from_row(with = "extension")]
+        #[table(sql_type = "TEXT")]
+        extension: Extension,
+        #[from_row(with = "extension")]
+        previous: Option<Extension>,
+        #[from_row(skip)]
+        selected: bool,
+    }
+
+    mod extension {
+        use rusqlite::types::{FromSql, FromSqlResult, ToSqlOutput, ValueRef};
+
+        #[derive(Debug, PartialEq)]
+        pub struct Extension(pub String);
+
+        pub fn from_sql(value: ValueRef<'_>) -> FromSqlResult<Extension> {
+            Ok(Extension(String::column_result(value)?.to_lowercase()))
+        }
+
+        pub fn to_sql(value: &Extension) -> rusqlite::Result<ToSqlOutput<'_>> {
+            Ok(ToSqlOutput::from(value.0.to_uppercase()))
+        }
+    }
+
+    use extension::Extension;
+
+    assert_eq!(
+        User::create_table_sql(),
+        r#"CREATE TABLE "user" ("id" INTEGER PRIMARY KEY, "email" TEXT NOT NULL UNIQUE)"#
+    );
+    assert_eq!(
+        Todo::create_table_sql(),
+        concat!(
+            r#"CREATE TABLE "todo" ("id" INTEGER PRIMARY KEY NOT NULL, "body" TEXT NOT NULL, "#,
+            r#""done" INTEGER NOT NULL DEFAULT 0, "score" REAL, "attachment" BLOB, "#,
+            r#""owner" INTEGER REFERENCES user(id), "priority" INT NOT NULL, "#,
+            r#""extension" TEXT NOT NULL, "previous" ANY) STRICT"#,
+        )
+    );
+
+    let connection = Connection::open_in_memory().unwrap();
+
+    connection.execute_batch(&User::create_table_sql()).unwrap();
+    connection.execute_batch(&Todo::create_table_sql()).unwrap();
+
+    let user = User {
+        id: None,
+        email: "john@example.com".to_string(),
+    }
+    .insert_returning(&connection)
+    .unwrap();
+
+    let todo = Todo {
+        id: 1,
+        text: "laundry".to_string(),
+        done: false,
+        score: Some(0.5),
+        attachment: None,
+        owner: user.id,
+        priority: 2,
+        extension: Extension("txt".to_string()),
+        previous: None,
+        selected: false,
+    };
+
+    todo.insert(&connection).unwrap();
+
+    // Fields using `with` are written by the `to_sql` function of the module.
+    let stored: String = connection
+        .query_row("SELECT extension FROM todo", params![], |row| row.get(0))
+        .unwrap();
+
+    assert_eq!(stored, "TXT");
+
+    let mut todo = Todo::get_by_id(&connection, 1).unwrap().unwrap();
+
+    assert_eq!(todo.owner, Some(1));
+    assert_eq!(todo.extension.0, "txt");
+    assert_eq!(todo.previous, None);
+
+    todo.previous = Some(Extension("md".to_string()));
+    todo.update(&connection).unwrap();
+
+    let todo = Todo::get_by_id(&connection, 1).unwrap().unwrap();
+
+    assert_eq!(todo.previous, Some(Extension("md".to_string())));
+    assert!(!todo.selected);
+    assert_eq!(user.email, "john@example.com");
+}